@@ -1,6 +1,10 @@
 
 section .rodata
 unit_str: db 40,118,111,105,100,41
+true_str: db 116,114,117,101
+false_str: db 102,97,108,115,101
+bounds_str: db "panic: index out of bounds", 10
+bounds_str_len equ $ - bounds_str
 
 section .text
 extern Main?main
@@ -36,6 +40,22 @@ Rt?print_unit:
     push qword 6
     call Rt?print_str
     ret
+global Rt?print_bool
+Rt?print_bool:
+    mov rax, [rsp+8]
+    test rax, rax
+    jz .false
+    lea rax, [true_str]
+    push rax
+    push qword 4
+    call Rt?print_str
+    ret 8
+.false:
+    lea rax, [false_str]
+    push rax
+    push qword 5
+    call Rt?print_str
+    ret 8
 global Rt?print_char
 Rt?print_char:
     mov rax, 1
@@ -92,4 +112,31 @@ Rt?print_u64:
 
     add rsp, 24
     ret 8
+global Rt?print_i64
+Rt?print_i64:
+    mov rax, [rsp+8] ; get arg
+    test rax, rax
+    jns .digits
+
+    push rax
+    push '-'
+    call Rt?print_char
+    pop rax
+    neg rax
+
+.digits:
+    push rax
+    call Rt?print_u64
+    ret 8
 
+global Rt?panic_bounds
+Rt?panic_bounds:
+    mov rax, 1              ; sys_write
+    mov rdi, 2              ; stderr
+    lea rsi, [bounds_str]
+    mov rdx, bounds_str_len
+    syscall
+
+    mov rax, 60             ; sys_exit
+    mov rdi, 101
+    syscall
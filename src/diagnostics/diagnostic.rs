@@ -0,0 +1,59 @@
+use core::fmt;
+
+use crate::lexer::tokens::Span;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str, // stable PascalCase code, e.g. `BadPackageName`
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, span: Span, code: &'static str, message: &str) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.to_string(),
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(span: Span, code: &'static str, message: &str) -> Self {
+        Self::new(Severity::Error, span, code, message)
+    }
+
+    pub fn warning(span: Span, code: &'static str, message: &str) -> Self {
+        Self::new(Severity::Warning, span, code, message)
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
@@ -0,0 +1,136 @@
+use std::rc::Rc;
+
+use crate::{diagnostics::{diagnostic::Diagnostic, source_file::SourceFile}, flags::ErrorFormat};
+
+
+pub struct DiagnosticEmitter {
+    source: Rc<SourceFile>,
+    format: ErrorFormat,
+    err_count: usize,
+    warn_count: usize,
+}
+
+impl DiagnosticEmitter {
+    pub fn new(source: Rc<SourceFile>, format: ErrorFormat) -> Self {
+        Self {
+            source,
+            format,
+            err_count: 0,
+            warn_count: 0,
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.err_count > 0
+    }
+
+    pub fn err_count(&self) -> usize {
+        self.err_count
+    }
+
+    pub fn warn_count(&self) -> usize {
+        self.warn_count
+    }
+
+    pub fn emit(&mut self, diagnostic: Diagnostic) {
+        if diagnostic.is_error() {
+            self.err_count += 1;
+        } else {
+            self.warn_count += 1;
+        }
+
+        match self.format {
+            ErrorFormat::Human => println!("{}", self.render(&diagnostic)),
+            ErrorFormat::Json => println!("{}", self.render_json(&diagnostic)),
+        }
+    }
+
+    /// Renders as a single line JSON object:
+    ///
+    /// ```text
+    /// {"severity":"error","code":"...","message":"...","file":"...",
+    ///  "span":{"start":{"line":1,"col":1},"end":{"line":1,"col":4}},"notes":[]}
+    /// ```
+    pub fn render_json(&self, diagnostic: &Diagnostic) -> String {
+        let span = diagnostic.span;
+        let notes: Vec<String> = diagnostic.notes.iter()
+            .map(|note| json_string(note))
+            .collect();
+
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"span\":{{\"start\":{{\"line\":{},\"col\":{}}},\"end\":{{\"line\":{},\"col\":{}}}}},\"notes\":[{}]}}",
+            json_string(&diagnostic.severity.to_string()),
+            json_string(diagnostic.code),
+            json_string(&diagnostic.message),
+            json_string(self.source.get_name()),
+            span.start.line, span.start.col,
+            span.end.line, span.end.col,
+            notes.join(","),
+        )
+    }
+
+    /// Renders in the same shape as rustc:
+    ///
+    /// ```text
+    /// error[BadPackageName]: message
+    ///  --> file.fn:3:9
+    ///   |
+    /// 3 | package my_pkg;
+    ///   |         ^^^^^^
+    ///   = note: ...
+    /// ```
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let span = diagnostic.span;
+        let line_num = span.start.line.to_string();
+        let gutter = " ".repeat(line_num.len());
+
+        let mut out = format!("{}[{}]: {}\n", diagnostic.severity, diagnostic.code, diagnostic.message);
+        out += &format!("{gutter}--> {}:{}:{}\n", self.source.get_name(), span.start.line, span.start.col);
+
+        if let Some(line) = self.source.get_line(span.start.line) {
+            let start_col = (span.start.col.max(1) - 1) as usize;
+            let line_len = line.chars().count();
+
+            // underline until the end of the span, or the end of the line for multi-line spans
+            let end_col = if span.end.line == span.start.line {
+                (span.end.col.max(1) - 1) as usize
+            } else {
+                line_len
+            };
+            let caret_count = end_col.saturating_sub(start_col).max(1);
+
+            // keep tabs so the caret lines up with the source line
+            let padding: String = line.chars().take(start_col)
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            out += &format!("{gutter} |\n");
+            out += &format!("{line_num} | {line}\n");
+            out += &format!("{gutter} | {padding}{}", "^".repeat(caret_count));
+        }
+
+        for note in &diagnostic.notes {
+            out += &format!("\n{gutter} = note: {note}");
+        }
+
+        out += "\n";
+        out
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut result = String::from("\"");
+    for ch in string.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
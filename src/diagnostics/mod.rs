@@ -0,0 +1,3 @@
+pub mod diagnostic;
+pub mod emitter;
+pub mod source_file;
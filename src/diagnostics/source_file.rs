@@ -0,0 +1,55 @@
+use std::{fs, rc::Rc};
+
+
+pub struct SourceFile {
+    name: String,
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: String, text: String) -> Rc<Self> {
+        let mut line_starts = vec![0];
+        for (i, ch) in text.char_indices() {
+            if ch == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+
+        Rc::new(Self {
+            name,
+            text,
+            line_starts,
+        })
+    }
+
+    pub fn from_file(file_name: &str) -> Rc<Self> {
+        let text = fs::read_to_string(file_name).unwrap_or_else(|_| {
+            println!("{file_name}");
+            "public main = () { print (\"Hello, furn!\") }".to_string()
+        });
+
+        Self::new(file_name.to_string(), text)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Line numbers start at 1, the returned line has no trailing newline
+    pub fn get_line(&self, line: isize) -> Option<&str> {
+        if line < 1 {
+            return None
+        }
+
+        let index = (line - 1) as usize;
+        let start = *self.line_starts.get(index)?;
+        let end = self.line_starts.get(index + 1).copied().unwrap_or(self.text.len());
+
+        Some(self.text[start..end].trim_end_matches(['\n', '\r']))
+    }
+}
@@ -9,16 +9,23 @@ pub enum CompilationTarget {
     Windows, // windows is ahh
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Human,
+    Json, // one object per line
+}
+
 #[derive(Clone)]
 pub struct Flags {
     args: VecDeque<String>,
     pub program_name: String,
-    pub file_name: Option<String>,
+    pub file_names: Vec<String>, // one package per file
     pub optimization_level: Option<MaybeInf<u32>>,
     pub target: Option<CompilationTarget>,
     pub print_ir: bool,
     pub output_file_name: Option<String>,
     pub compile_iters: Option<u64>,
+    pub error_format: ErrorFormat,
 }
 
 impl Flags {
@@ -29,12 +36,13 @@ impl Flags {
         Self {
             args,
             program_name,
-            file_name: None,
+            file_names: Vec::new(),
             optimization_level: None,
             target: None,
             print_ir: false,
             output_file_name: None,
             compile_iters: None,
+            error_format: ErrorFormat::Human,
         }
     }
 
@@ -65,13 +73,13 @@ impl Flags {
 
     }
 
-    pub fn parse_args(args: Args) -> Self {
+    pub fn parse_args(args: Args) -> Result<Self, String> {
         let mut flags = Self::with_args(args);
-        flags.parse();
-        flags
+        flags.parse()?;
+        Ok(flags)
     }
 
-    pub fn parse(&mut self) {
+    pub fn parse(&mut self) -> Result<(), String> {
 
         self.split_args();
 
@@ -122,9 +130,19 @@ impl Flags {
                     }
                 }
 
-                _ => self.file_name = Some(arg),
+                _ if arg.starts_with("--error-format=") => {
+                    self.error_format = match arg.trim_start_matches("--error-format=") {
+                        "human" => ErrorFormat::Human,
+                        "json" => ErrorFormat::Json,
+                        format => return Err(format!("unknown error format `{format}`, expected `human` or `json`")),
+                    };
+                }
+
+                _ => self.file_names.push(arg),
             }
         }
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,53 @@
+use std::hash::{Hash, Hasher};
+
+use crate::ir_gen::typeval::TypeVal;
+
+/// A `class` type, two classes are only the same type if they come from the same declaration
+#[derive(Debug)]
+pub struct ClassInfo {
+    pub id: usize,
+    pub name: Option<String>,
+    pub fields: Vec<(String, TypeVal)>,
+}
+
+impl ClassInfo {
+    pub fn new(id: usize, name: Option<String>, fields: Vec<(String, TypeVal)>) -> Self {
+        Self {
+            id,
+            name,
+            fields,
+        }
+    }
+
+    pub fn stack_size_of(&self) -> usize {
+        self.fields.iter().map(|(_, typeval)| typeval.stack_size_of()).sum()
+    }
+
+    /// Returns the field and its offset, fields are laid out in push order
+    /// (the first field is pushed first, so it ends up deepest in the stack)
+    pub fn field(&self, name: &str) -> Option<(usize, &TypeVal)> {
+        let mut offset = 0;
+        for (field_name, typeval) in &self.fields {
+            if field_name == name {
+                return Some((offset, typeval))
+            }
+            offset += typeval.stack_size_of();
+        }
+
+        None
+    }
+}
+
+impl PartialEq for ClassInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for ClassInfo {}
+
+impl Hash for ClassInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
@@ -104,7 +104,8 @@ impl<'a> CompiledProgram<'a> {
         
         let string_pos = self.static_strings_len;
         self.static_strings.push((string, string_pos));
-        self.static_strings_len += string.len();
+        // an empty string still takes a byte, so every label stays unique
+        self.static_strings_len += string.len().max(1);
         string_pos
     }
 
@@ -113,6 +114,11 @@ impl<'a> CompiledProgram<'a> {
     }
 
     pub fn shift_nodes(&mut self, range: RangeInclusive<usize>) {
+        // nothing was generated
+        if range.is_empty() {
+            return
+        }
+
         let start = *range.start();
         let end = (*range.end()).min(self.ir.len().saturating_sub(1));
 
@@ -129,104 +135,60 @@ impl<'a> CompiledProgram<'a> {
         self.realign_addresses(pos, 1, true);
     }
 
-    pub fn realign_stack_offsets(&mut self, pos: usize, stack_offset: usize, alignment: usize) {
-        let mut i: usize = pos;
-        while let Some(node) = self.ir.get_mut(i) {
-            match node {
-
-                    IRNode::Pop64ToStack(offset) => {
-                        if *offset > stack_offset {
-                            *offset += alignment;
-                        }
-                    },
-
-                    IRNode::Load64ToStack(_, offset) => {
-                        if *offset >= stack_offset {
-                            *offset += alignment;
-                        }
-                    },
-
-                    IRNode::StackReadPush64(offset) => {
-                        if *offset >= stack_offset {
-                            *offset += alignment;
-                        }
-                    },
-
-                    IRNode::GlobalReadLoad64ToStack(_, offset) => {
-                        if *offset >= stack_offset {
-                            *offset += alignment;
-                        }
-                    },
-
-                    IRNode::StackReadLoad64ToStack(src_offset, dst_offset) => {
-                        if *src_offset >= stack_offset {
-                            *src_offset += alignment;
-                        }
-                        if *dst_offset >= stack_offset {
-                            *dst_offset += alignment;
-                        }
-                    },
-                    
-                    IRNode::PushStackPointer(offset) => {
-                        println!("before");
-                        println!("{offset}");
-                        if *offset >= stack_offset {
-                            *offset += alignment;
-                        }
-                        println!("after");
-                        println!("{offset}");
-                        println!("{alignment}");
-                        println!("end");
-                    },
-
-                    _ => (),
-                }
-
-            i += 1;
+    /// Where an address ends up after `count` nodes were inserted at (or removed from) `pos`,
+    /// jump targets are always labels (`Nop`) so an insertion at a target moves the target along
+    fn adjust(target: i64, pos: i64, count: i64, insert: bool) -> i64 {
+        if insert {
+            if target >= pos { target + count } else { target }
+        } else if target >= pos + count {
+            target - count
+        } else if target >= pos {
+            pos // pointed at a removed node, now points at whatever came after it
+        } else {
+            target
         }
     }
 
-    fn adjust(max: i64, offset: &mut i64, pos: usize, count: usize, insert: bool) {
-        if *offset >= pos as i64 {
-            if insert {
-                *offset += count as i64;
-            } else {
-                *offset -= count as i64;
-            }
-        }
-
-        // make sure it doesnt point out of bounds
-        *offset = (*offset).clamp(0, max);
-    }
-
     fn realign_addresses(&mut self, pos: usize, count: usize, switch: bool) {
-        let max = (self.ir.len().saturating_sub(1)) as i64;
+        let (pos, count) = (pos as i64, count as i64);
 
         for global in self.globals.iter_mut() {
             match &mut global.init {
                 CTimeVal::Function { address, .. } => {
-                    if *address > pos {
-                        if switch {
-                            *address += count;
-                        } else {
-                            *address = address.saturating_sub(count);
-                        }
-                    }
+                    *address = Self::adjust(*address as i64, pos, count, switch) as usize;
                 }
 
                 _ => (),
             }
         }
 
-        for node in &mut self.ir {
+        for (i, node) in self.ir.iter_mut().enumerate() {
+            let i = i as i64;
+
+            // offsets are relative, so find where this node was before the change
+            let prev_i = if switch {
+                if i >= pos + count {
+                    i - count
+                } else if i >= pos {
+                    continue // newly inserted nodes already have the right offset
+                } else {
+                    i
+                }
+            } else if i >= pos {
+                i + count
+            } else {
+                i
+            };
+
             match node {
 
                 IRNode::CallFromOffset(offset)
                 | IRNode::PushAddressFromOffset(offset)
                 | IRNode::JumpFromOffset(offset)
                 | IRNode::JumpIfNot64FromOffset(offset)
-                | IRNode::JumpIfNotEqConst64FromOffset(_, offset) => {
-                    Self::adjust(max, offset, pos, count, switch);
+                | IRNode::JumpIfNotEqConst64FromOffset(_, offset)
+                | IRNode::JumpIfNotCmp64FromOffset(_, offset) => {
+                    *offset = Self::adjust(prev_i + *offset, pos, count, switch) - i;
                 },
 
                 _ => (),
@@ -1,4 +1,4 @@
-use crate::ir_gen::ctimeval::CTimeVal;
+use crate::ir_gen::{ctimeval::CTimeVal, typeval::TypeVal};
 
 #[derive(Clone)]
 pub struct GlobalInfo<'a> {
@@ -7,16 +7,18 @@ pub struct GlobalInfo<'a> {
     pub is_exported: bool,
     pub init: CTimeVal,
     pub is_const: bool,
+    pub typeval: TypeVal,
 }
 
 impl<'a> GlobalInfo<'a> {
-    pub fn new(pos: usize, name: &'a str, is_exported: bool, init: CTimeVal, is_const: bool) -> Self {
+    pub fn new(pos: usize, name: &'a str, is_exported: bool, init: CTimeVal, is_const: bool, typeval: TypeVal) -> Self {
         Self {
             pos,
             name,
             is_exported,
             init,
             is_const,
+            typeval,
         }
     }
 }
@@ -1,6 +1,33 @@
 use crate::ir_gen::external::ExternalInfo;
 
 
+/// Comparison of the two 64 bit values on top of the stack, orderings are unsigned unless marked signed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    SignedLess,
+    SignedLessEq,
+    SignedGreater,
+    SignedGreaterEq,
+}
+
+impl Condition {
+    pub fn to_signed(self) -> Self {
+        match self {
+            Condition::Less => Condition::SignedLess,
+            Condition::LessEq => Condition::SignedLessEq,
+            Condition::Greater => Condition::SignedGreater,
+            Condition::GreaterEq => Condition::SignedGreaterEq,
+            _ => self,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum IRNode {
     Nop,
@@ -10,6 +37,7 @@ pub enum IRNode {
     CallFromOffset(i64),
     JumpIfNot64FromOffset(i64),
     JumpIfNotEqConst64FromOffset(u64, i64),
+    JumpIfNotCmp64FromOffset(Condition, i64),
     Return { params_size: usize },
     Push64(u64),
     Pop64ToStack(usize),
@@ -20,13 +48,34 @@ pub enum IRNode {
     StackDealloc(usize),
     GlobalReadPush64(usize),
     GlobalReadLoad64ToStack(usize, usize),
+    PopGlobal64(usize),
     StackReadPush64(usize),
     StackReadLoad64ToStack(usize, usize),
     PushStaticStringPointer(usize),
     PushStackPointer(usize),
+    PushGlobalPointer(usize),
     Deref64,
+    Deref8, // zero extends the byte
+    Deref(usize), // whole words, highest address first so the value ends up in stack order
+    PopToAddress(usize), // pops a value and the address under it, the value's top word goes to the address
+    IndexCheck64, // pops the length, panics if the index under it is not below it
     StackDeref64(usize),
     Add64,
     Sub64,
+    Mul64,
+    Div64,
+    Mod64,
+    IDiv64, // signed
+    IMod64, // signed
+    Neg64,
+    Not64,
+    Or64,
+    And64,
+    Xor64,
+    Shl64,
+    Shr64, // fills with zeros
+    Sar64, // fills with the sign bit
+    Truncate64 { size: usize, is_signed: bool }, // cuts the top value down to `size` bytes and extends it back to 64 bits
+    Cmp64(Condition),
 }
 
@@ -1,6 +1,6 @@
-use std::collections::{HashMap, VecDeque};
+use std::{collections::{HashMap, VecDeque}, ops::Range, rc::Rc};
 
-use crate::{ir_gen::{cmpld_program::CompiledProgram, ctimeval::CTimeVal, external::ExternalInfo, global::GlobalInfo, ir::IRNode, lifetime::Lifetime, scope::Scope, symbol::CmplSymbol, typeval::{TypeVal, TypeValEnum}, variable::Variable}, lexer::tokens::SourceLocation, parser::ast::{AstBlock, Expr, ExprEnum, IfKind, Operator, Stmt, StmtEnum}};
+use crate::{ir_gen::{class_info::ClassInfo, cmpld_program::CompiledProgram, ctimeval::CTimeVal, external::ExternalInfo, global::GlobalInfo, ir::{Condition, IRNode}, lifetime::Lifetime, loop_info::LoopInfo, scope::Scope, symbol::CmplSymbol, typeval::{TypeVal, TypeValEnum}, variable::Variable}, diagnostics::{diagnostic::Diagnostic, emitter::DiagnosticEmitter}, lexer::tokens::Span, parser::{ast::{AstBlock, Expr, ExprEnum, IfKind, Operator, SelectArm, SelectPattern, Stmt, StmtEnum, Capture}, int_kind::IntKind}};
 
 const ADDRESS_SIZE: usize = 8;
 const SIZE_64: usize = 8;
@@ -60,13 +60,21 @@ pub struct IRGen<'a> {
     global_sz: usize,
     symbol_cache: HashMap<*const Expr, CmplSymbol>,
     lifetime_id_counter: usize,
-    err_count: usize,
+    diag: DiagnosticEmitter,
     unsafe_depth: usize,
     diagnostics_lock: usize,
+    loops: Vec<LoopInfo>,
+    trial_types: HashMap<*const Expr, TypeVal>, // types only known after generating the expression
+    closure_addresses: HashMap<*const Expr, usize>, // where the body of each closure starts
+    enclosing_scopes: Vec<VecDeque<Scope<Variable>>>, // locals of the functions around the one being generated
+    methods: HashMap<(usize, String), CmplSymbol>, // by class id and name
+    method_classes: Vec<TypeVal>, // classes whose methods are being generated, `self` is typed by the last one
+    class_id_counter: usize,
+    interfaces: HashMap<String, &'a [Stmt]>, // declarations read from the interface files of other packages
 }
 
 impl<'a> IRGen<'a> {
-    pub fn new() -> Self {
+    pub fn new(diag: DiagnosticEmitter) -> Self {
         Self {
             cprog: CompiledProgram::new(),
             global_scope: Scope::new(),
@@ -75,27 +83,43 @@ impl<'a> IRGen<'a> {
             global_sz: 0,
             symbol_cache: HashMap::new(),
             lifetime_id_counter: 0,
-            err_count: 0,
+            diag,
             unsafe_depth: 0,
             diagnostics_lock: 0,
+            loops: Vec::new(),
+            trial_types: HashMap::new(),
+            closure_addresses: HashMap::new(),
+            enclosing_scopes: Vec::new(),
+            methods: HashMap::new(),
+            method_classes: Vec::new(),
+            class_id_counter: 0,
+            interfaces: HashMap::new(),
         }
     }
 
-    fn emit_diagnostic(&mut self, loc: &SourceLocation, message: &str) {
+    /// Makes the interface of another package available to `import package Name;`
+    pub fn add_interface(&mut self, package_name: String, decls: &'a [Stmt]) {
+        self.interfaces.insert(package_name, decls);
+    }
+
+    fn emit(&mut self, diagnostic: Diagnostic) {
         if self.diagnostics_lock > 0 {
             return
         }
 
-        if message.contains("⚠️") {
-            println!("(Line {}, Col {}) [Warning]: {}", loc.line, loc.col, message);
-        } else {
-            self.err_count += 1;
-            println!("(Line {}, Col {}) [Error]: ❗ {}", loc.line, loc.col, message);
-        }
+        self.diag.emit(diagnostic);
+    }
+
+    fn emit_diagnostic(&mut self, span: &Span, code: &'static str, message: &str) {
+        self.emit(Diagnostic::error(*span, code, message));
+    }
+
+    fn emit_warning(&mut self, span: &Span, code: &'static str, message: &str) {
+        self.emit(Diagnostic::warning(*span, code, message));
     }
 
     pub fn has_errors(&self) -> bool {
-        self.err_count > 0
+        self.diag.has_errors()
     }
 
     fn open_scope(&mut self) {
@@ -106,27 +130,6 @@ impl<'a> IRGen<'a> {
         self.unsafe_depth > 0
     }
 
-    fn scope_locals_stackp(&self) -> Option<usize> {
-        let mut stack_loc = self.stack_sz;
-        if let Some(scope) = self.scopes.front() {
-            for var in scope.iter() {
-                if !var.is_alias {
-                    if let Some(_) = var.stack_loc {
-                        stack_loc -= var.typeval.size_of();
-                    }
-                }
-            }
-        } else {
-            unreachable!()
-        }
-
-        if stack_loc == self.stack_sz {
-            None
-        } else {
-            Some(stack_loc)
-        }
-    }
-
     fn close_scope(&mut self) {
         if let Some(scope) = self.scopes.pop_front() {
 
@@ -135,7 +138,7 @@ impl<'a> IRGen<'a> {
             for var in scope.iter() {
                 if !var.is_alias {
                     if let Some(_) = var.stack_loc {
-                        locals_size_total += var.typeval.size_of()
+                        locals_size_total += var.typeval.stack_size_of()
                     }
                 }
             }
@@ -155,7 +158,7 @@ impl<'a> IRGen<'a> {
             for var in scope.iter() {
                 if !var.is_alias {
                     if let Some(_) = var.stack_loc {
-                        locals_size_total += var.typeval.size_of()
+                        locals_size_total += var.typeval.stack_size_of()
                     }
                 }
             }
@@ -218,19 +221,23 @@ impl<'a> IRGen<'a> {
 
     fn new_global_pos(&mut self, typeval: &TypeVal) -> usize {
         let prev = self.global_sz;
-        self.global_sz += typeval.size_of();
+        self.global_sz += typeval.stack_size_of();
         prev
     }
 
     pub fn generate(&mut self, ast: &'a Vec<Stmt>) -> &mut CompiledProgram<'a> {
         let mut rt_map = HashMap::new();
 
+        // called by failed bounds checks, not visible to programs
+        self.cprog.add_external(ExternalInfo::new("panic_bounds".to_string(), "Rt".to_string(), true));
+
         self.cprog.add_external(ExternalInfo::new("print_str".to_string(), "Rt".to_string(), true));
         let print_str = Variable {
             name: "print_str".to_string(),
             typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::StringSlice.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: Some(ExternalInfo::new("print_str".to_string(), "Rt".to_string(), true)),
             is_alias: false,
@@ -245,6 +252,7 @@ impl<'a> IRGen<'a> {
             typeval: TypeValEnum::FunctionPointer(vec![], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: Some(ExternalInfo::new("print_newline".to_string(), "Rt".to_string(), true)),
             is_alias: false,
@@ -259,6 +267,7 @@ impl<'a> IRGen<'a> {
             typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::Unit.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: Some(ExternalInfo::new("print_unit".to_string(), "Rt".to_string(), true)),
             is_alias: false,
@@ -267,26 +276,43 @@ impl<'a> IRGen<'a> {
         };
         rt_map.insert("print_unit".to_string(), print_unit.clone());
 
+        self.cprog.add_external(ExternalInfo::new("print_bool".to_string(), "Rt".to_string(), true));
+        let print_bool = Variable {
+            name: "print_bool".to_string(),
+            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::Bool.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
+            global_pos: None,
+            stack_loc: None,
+            indirect: None,
+            const_val: None,
+            external: Some(ExternalInfo::new("print_bool".to_string(), "Rt".to_string(), true)),
+            is_alias: false,
+            lifetime: self.lifetime_here(),
+            is_unsafe: false,
+        };
+        rt_map.insert("print_bool".to_string(), print_bool.clone());
+
         self.cprog.add_external(ExternalInfo::new("print_char".to_string(), "Rt".to_string(), true));
-        let print_var = Variable {
+        let print_char = Variable {
             name: "print_char".to_string(),
-            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::UInt64.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
+            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::Char.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: Some(ExternalInfo::new("print_char".to_string(), "Rt".to_string(), true)),
             is_alias: false,
             lifetime: self.lifetime_here(),
             is_unsafe: false,
         };
-        rt_map.insert("print_char".to_string(), print_var);
+        rt_map.insert("print_char".to_string(), print_char.clone());
 
         self.cprog.add_external(ExternalInfo::new("print_digit".to_string(), "Rt".to_string(), true));
         let print_var = Variable {
             name: "print_digit".to_string(),
-            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::UInt64.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
+            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::Int(IntKind::U64).to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: Some(ExternalInfo::new("print_digit".to_string(), "Rt".to_string(), true)),
             is_alias: false,
@@ -298,9 +324,10 @@ impl<'a> IRGen<'a> {
         self.cprog.add_external(ExternalInfo::new("print_u64".to_string(), "Rt".to_string(), true));
         let print_u64 = Variable {
             name: "print_u64".to_string(),
-            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::UInt64.to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
+            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::Int(IntKind::U64).to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: Some(ExternalInfo::new("print_u64".to_string(), "Rt".to_string(), true)),
             is_alias: false,
@@ -309,40 +336,76 @@ impl<'a> IRGen<'a> {
         };
         rt_map.insert("print_u64".to_string(), print_u64.clone());
 
-        let mut dynamic_print_map = HashMap::new();
-
-        dynamic_print_map.insert(TypeValEnum::StringSlice.to_tval(), CmplSymbol {
-            const_val: None,
-            typeval: print_str.typeval.clone(),
-            var: Some(print_str),
-            lifetime: None,
-            is_unsafe: false,
-        });
-
-        dynamic_print_map.insert(TypeValEnum::Unit.to_tval(), CmplSymbol {
+        self.cprog.add_external(ExternalInfo::new("print_i64".to_string(), "Rt".to_string(), true));
+        let print_i64 = Variable {
+            name: "print_i64".to_string(),
+            typeval: TypeValEnum::FunctionPointer(vec![TypeValEnum::Int(IntKind::I64).to_tval()], Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
+            global_pos: None,
+            stack_loc: None,
+            indirect: None,
             const_val: None,
-            typeval: print_unit.typeval.clone(),
-            var: Some(print_unit),
-            lifetime: None,
+            external: Some(ExternalInfo::new("print_i64".to_string(), "Rt".to_string(), true)),
+            is_alias: false,
+            lifetime: self.lifetime_here(),
             is_unsafe: false,
-        });
+        };
+        rt_map.insert("print_i64".to_string(), print_i64.clone());
 
-        dynamic_print_map.insert(TypeValEnum::UInt64.to_tval(), CmplSymbol {
-            const_val: None,
-            typeval: print_u64.typeval.clone(),
-            var: Some(print_u64),
-            lifetime: None,
-            is_unsafe: false,
-        });
+        // the argument types select the function, in the same order as `select { ... }`
+        let print_candidates = vec![
+            CmplSymbol {
+                const_val: None,
+                typeval: print_str.typeval.clone(),
+                var: Some(print_str),
+                lifetime: None,
+                is_unsafe: false,
+            },
+            CmplSymbol {
+                const_val: None,
+                typeval: print_unit.typeval.clone(),
+                var: Some(print_unit),
+                lifetime: None,
+                is_unsafe: false,
+            },
+            CmplSymbol {
+                const_val: None,
+                typeval: print_u64.typeval.clone(),
+                var: Some(print_u64),
+                lifetime: None,
+                is_unsafe: false,
+            },
+            CmplSymbol {
+                const_val: None,
+                typeval: print_i64.typeval.clone(),
+                var: Some(print_i64),
+                lifetime: None,
+                is_unsafe: false,
+            },
+            CmplSymbol {
+                const_val: None,
+                typeval: print_bool.typeval.clone(),
+                var: Some(print_bool),
+                lifetime: None,
+                is_unsafe: false,
+            },
+            CmplSymbol {
+                const_val: None,
+                typeval: print_char.typeval.clone(),
+                var: Some(print_char),
+                lifetime: None,
+                is_unsafe: false,
+            },
+        ];
 
         let print_var = Variable {
             name: "print".to_string(),
             typeval: TypeValEnum::Unit.to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: Some(
                 CTimeVal::DynamicFnDispatcher {
-                    map: dynamic_print_map,
+                    candidates: print_candidates,
                     meta_funcs: Box::new(
                         (
                             CmplSymbol::void(),
@@ -371,6 +434,7 @@ impl<'a> IRGen<'a> {
             typeval: TypeValEnum::Unit.to_tval(),
             global_pos: None,
             stack_loc: None,
+            indirect: None,
             const_val: Some(rt_namespace),
             external: None,
             is_alias: false,
@@ -385,9 +449,13 @@ impl<'a> IRGen<'a> {
         }
 
         if self.cprog.get_package_name().is_none() {
-            for global in self.cprog.clone().globals_iter() {
-                if global.is_exported && global.name != "main" {
-                    self.emit_diagnostic(&SourceLocation::garbage(), "unable to export any symbols if a package name was never declared");
+            for stmt in ast {
+                match stmt.as_enum() {
+                    StmtEnum::ConstDecl(name, _, _, true)
+                    | StmtEnum::VarDecl(name, _, _, true) if name != "main" => {
+                        self.emit_diagnostic(stmt.get_span(), "NoPackageName", "unable to export any symbols if a package name was never declared");
+                    },
+                    _ => (),
                 }
             }
         }
@@ -399,6 +467,22 @@ impl<'a> IRGen<'a> {
         self.cprog.app_node(node);
     }
 
+    /// Every jump lands on a `Nop`, the optimizer never merges those so targets stay put
+    fn emit_label(&mut self) -> usize {
+        self.emit_node(IRNode::Nop);
+        self.cprog.ir_pos()
+    }
+
+    fn patch_jump(&mut self, jump_pos: usize, target: usize) {
+        let offset = (target as i64) - (jump_pos as i64);
+        match self.cprog.node_mut_at(jump_pos) {
+            IRNode::JumpFromOffset(jump_offset)
+            | IRNode::JumpIfNot64FromOffset(jump_offset)
+            | IRNode::JumpIfNotEqConst64FromOffset(_, jump_offset) => *jump_offset = offset,
+            _ => unreachable!(),
+        }
+    }
+
     fn gen_stmt(&mut self, stmt: &'a Stmt) {
         match stmt.as_enum() {
             StmtEnum::Expr(expr, _) => {
@@ -415,24 +499,87 @@ impl<'a> IRGen<'a> {
 
             StmtEnum::PackageDecl(name) => {
                 if let Some(name) = self.cprog.get_package_name() {
-                    self.emit_diagnostic(stmt.get_loc(), format!("package was previously declared as `{name}`").as_str());
+                    self.emit_diagnostic(stmt.get_span(), "PackageRedeclared", format!("package was previously declared as `{name}`").as_str());
                 } else {
                     if !is_pascal_case(name) {
-                        self.emit_diagnostic(stmt.get_loc(), format!("|BadPackageName| ⚠️ a package name should be PascalCase: `package {};`", as_pascal_case(name)).as_str());
+                        self.emit_warning(stmt.get_span(), "BadPackageName", format!("a package name should be PascalCase: `package {};`", as_pascal_case(name)).as_str());
                     }
                     self.cprog.set_package_name(Some(name));
                 }
             },
 
+            StmtEnum::ImportDecl(package_name, decls) => {
+                if self.has_local_scope() {
+                    self.emit_diagnostic(stmt.get_span(), "ImportNotGlobal", "imports must be in the global scope");
+                    return
+                }
+
+                let interface = self.interfaces.get(package_name).copied();
+                let (decls, interface) = match (decls, interface) {
+                    (Some(decls), interface) => (decls.as_slice(), interface),
+                    (None, Some(interface)) => (interface, None), // nothing to check against
+                    (None, None) => {
+                        self.emit(
+                            Diagnostic::error(*stmt.get_span(), "MissingInterface", format!("no interface found for package `{package_name}`").as_str())
+                                .with_note("compile the package first, or declare the imports in `{ ... }`")
+                        );
+                        return
+                    },
+                };
+
+                for decl in decls {
+                    let (name, type_expr, is_const) = Self::import_decl_parts(decl);
+                    let typeval = self.resolve_expr(type_expr).typeval;
+
+                    // hand written declarations must agree with what the package really exports
+                    if let Some(interface) = interface {
+                        let exported = interface.iter().map(Self::import_decl_parts).find(|(other_name, ..)| *other_name == name);
+                        if let Some((_, other_type_expr, other_is_const)) = exported {
+                            let other_typeval = self.resolve_expr(other_type_expr).typeval;
+                            if other_is_const != is_const {
+                                let kind = |is_const| if is_const { "a constant" } else { "mutable" };
+                                self.emit_diagnostic(decl.get_span(), "ImportMismatch", format!("`{name}` is imported as {}, but package `{package_name}` exports it as {}", kind(is_const), kind(other_is_const)).as_str());
+                            } else if other_typeval != typeval {
+                                self.emit_diagnostic(type_expr.get_span(), "ImportMismatch", format!("`{name}` is imported as `{typeval}`, but package `{package_name}` exports `{other_typeval}`").as_str());
+                            }
+                        } else {
+                            self.emit_diagnostic(decl.get_span(), "UnknownImport", format!("package `{package_name}` does not export `{name}`").as_str());
+                        }
+                    }
+
+                    // the linker resolves these, `Pkg?name` for constants and `Pkg$name` for mutable globals
+                    let external = ExternalInfo::new(name.clone(), package_name.clone(), is_const);
+                    self.cprog.add_external(external.clone());
+
+                    let var = Variable {
+                        name: name.clone(),
+                        typeval,
+                        global_pos: None,
+                        stack_loc: None,
+                        indirect: None,
+                        const_val: None,
+                        external: Some(external),
+                        is_alias: false,
+                        lifetime: self.lifetime_here(),
+                        is_unsafe: false,
+                    };
+                    self.add_global(var);
+                }
+            },
+
             StmtEnum::AliasDecl(alias_name, expr, is_call) => {
+                // a missing target is reported once, as `AliasNotFound`
+                self.diagnostics_lock += 1;
                 let var = self.resolve_expr(expr).var;
+                self.diagnostics_lock -= 1;
                 if let Some(var) = var {
+                    // the binding is still created so later uses don't cascade into more errors
                     match var.typeval.as_enum() {
                         TypeValEnum::FunctionPointer(..) => if !(*is_call) {
-                            todo!("add compile errors (add `()` to function alias)");
+                            self.emit_diagnostic(stmt.get_span(), "AliasMissingCall", format!("`{}` is a function, add `()` to alias it", var.name).as_str());
                         },
                         _ => if *is_call {
-                            todo!("add compile errors (remove `()` from this alias)");
+                            self.emit_diagnostic(stmt.get_span(), "AliasExtraCall", format!("`{}` is not a function, remove `()` from this alias", var.name).as_str());
                         },
                     }
 
@@ -448,19 +595,41 @@ impl<'a> IRGen<'a> {
                         self.add_global(new_binding);
                     }
                 } else {
-                    todo!("add compile errors (alias failed, name does not exist here)");
+                    self.emit_diagnostic(expr.get_span(), "AliasNotFound", "nothing to alias, name does not exist here");
                 }
             },
 
-            StmtEnum::ConstDecl(name, init, type_expr, is_exported) => self.gen_decl(name, init, type_expr, true, *is_exported, stmt.get_loc()),
-            StmtEnum::VarDecl(name, init, type_expr, is_exported) => self.gen_decl(name, init, type_expr, false, *is_exported, stmt.get_loc()),
+            StmtEnum::ConstDecl(name, init, type_expr, is_exported) => self.gen_decl(name, init, type_expr, true, *is_exported, stmt.get_span()),
+            StmtEnum::VarDecl(name, init, type_expr, is_exported) => self.gen_decl(name, init, type_expr, false, *is_exported, stmt.get_span()),
         }
     }
 
-    fn gen_decl(&mut self, name: &'a str, init: &'a Option<Expr>, type_expr: &'a Option<Expr>, is_const: bool, is_exported: bool, loc: &SourceLocation) {
+    fn import_decl_parts(decl: &'a Stmt) -> (&'a String, &'a Expr, bool) {
+        match decl.as_enum() {
+            StmtEnum::ConstDecl(name, None, Some(type_expr), _) => (name, type_expr, true),
+            StmtEnum::VarDecl(name, None, Some(type_expr), _) => (name, type_expr, false),
+            _ => unreachable!(),
+        }
+    }
+
+    fn gen_decl(&mut self, name: &'a str, init: &'a Option<Expr>, type_expr: &'a Option<Expr>, is_const: bool, is_exported: bool, span: &Span) {
 
         if self.has_local_scope() && is_exported {
-            self.emit_diagnostic(loc, "exports must be global variables");
+            self.emit_diagnostic(span, "ExportNotGlobal", "exports must be global variables");
+        }
+
+        // classes take the name of their declaration
+        if let Some(init) = init
+            && let ExprEnum::Class(fields) = init.as_enum()
+            && !self.symbol_cache.contains_key(&(init as *const Expr)) {
+            let symbol = self.resolve_class(fields, Some(name));
+            self.symbol_cache.insert(init as *const Expr, symbol);
+        }
+
+        if let Some(init) = init
+            && let Some(type_expr) = type_expr {
+            let declared = self.resolve_expr(type_expr).typeval;
+            self.hand_down_array_type(init, &declared);
         }
 
         let typeval = if let Some(type_expr) = type_expr {
@@ -468,7 +637,7 @@ impl<'a> IRGen<'a> {
         } else if let Some(init) = init {
             self.resolve_expr(init).typeval
         } else {
-            self.emit_diagnostic(loc, "type cannot be inferred here, defaults to `void`");
+            self.emit_diagnostic(span, "CannotInferType", "type cannot be inferred here, defaults to `void`");
             TypeValEnum::Unit.to_tval()
         };
 
@@ -480,6 +649,7 @@ impl<'a> IRGen<'a> {
             typeval: typeval.clone(),
             global_pos,
             stack_loc: None,
+            indirect: None,
             const_val: None,
             external: None,
             is_alias: false,
@@ -491,30 +661,40 @@ impl<'a> IRGen<'a> {
 
             let symbol = self.resolve_expr(expr);
             if type_expr.is_none() {
-                var.typeval = symbol.typeval;
+                var.typeval = symbol.typeval.clone();
+            } else {
+                self.check_int_conversion(&symbol, &var.typeval, expr.get_span());
+                self.check_null_conversion(&symbol, &var.typeval, expr.get_span());
             }
 
             if !var.is_unsafe && symbol.is_unsafe {
-                self.emit_diagnostic(expr.get_loc(), "variable must be in `unsafe` block");
+                self.emit_diagnostic(expr.get_span(), "UnsafeRequired", "variable must be in `unsafe` block");
             } else if let Some(other_lifetime) = symbol.lifetime {
                 if other_lifetime < var.lifetime {
                     // emit this error even in unsafe blocks
-                    self.emit_diagnostic(expr.get_loc(), "lifetime may not live until initialization");
+                    self.emit_diagnostic(expr.get_span(), "LifetimeTooShort", "lifetime may not live until initialization");
                 }
             }
 
             if symbol.const_val.is_some() && is_const && is_global_var {
                 var.const_val = symbol.const_val.clone();
+            } else if let Some(CTimeVal::Int(int)) = symbol.const_val && is_const {
+                // local integer constants fold like global ones
+                var.const_val = Some(CTimeVal::Int(int));
             }
             
             if is_global_var {
                 if let Some(const_val) = symbol.const_val {
-                    if is_exported {
-                        let global_info = GlobalInfo::new(global_pos.unwrap_or_default(), name, is_exported, const_val, is_const);
+                    // mutable globals always need storage, constants are inlined unless exported
+                    let is_storable = !matches!(const_val, CTimeVal::Type(..) | CTimeVal::Namespace(..) | CTimeVal::DynamicFnDispatcher { .. });
+                    if !is_storable && !is_const {
+                        self.emit_diagnostic(expr.get_span(), "NonConstGlobalInit", "global variable must be initialized with a value, not a type, namespace or overload set");
+                    } else if is_storable && (is_exported || !is_const) {
+                        let global_info = GlobalInfo::new(global_pos.unwrap_or_default(), name, is_exported, const_val, is_const, var.typeval.clone());
                         self.cprog.add_global(global_info);
                     }
-                } else {
-                    self.emit_diagnostic(expr.get_loc(), "global variable must be initialized with a compile-time constant");
+                } else if !self.check_const_overflow_deep(expr) {
+                    self.emit_diagnostic(expr.get_span(), "NonConstGlobalInit", "global variable must be initialized with a compile-time constant");
                 }
             } else if symbol.const_val.is_none() || !is_const || !is_global_var {
                 self.gen_expr(expr);
@@ -522,11 +702,11 @@ impl<'a> IRGen<'a> {
             }
 
         } else if is_exported {
-            self.emit_diagnostic(loc, "exported symbol is not initialized");
+            self.emit_diagnostic(span, "UninitializedExport", "exported symbol is not initialized");
         } else if !is_global_var {
             let result = self.push_zeroval(&typeval);
             if let Err(message) = result {
-                self.emit_diagnostic(type_expr.as_ref().unwrap().get_loc(), message.as_str());
+                self.emit_diagnostic(type_expr.as_ref().unwrap().get_span(), "NoZeroValue", message.as_str());
             }
 
             var.stack_loc = Some(self.stack_sz);
@@ -539,13 +719,18 @@ impl<'a> IRGen<'a> {
         }
     }
 
-    fn gen_const_val(&mut self, const_val: &CTimeVal) {
+    fn gen_const_val(&mut self, const_val: &CTimeVal, span: &Span) {
         match const_val {
             CTimeVal::Int(int) => {
                 self.emit_node(IRNode::Push64(*int as u64));
                 self.stack_sz += SIZE_64;
             },
 
+            CTimeVal::Bool(boolean) => {
+                self.emit_node(IRNode::Push64(*boolean as u64));
+                self.stack_sz += SIZE_64;
+            },
+
             CTimeVal::StringSlice(pointer, len) => {
                 self.emit_node(IRNode::PushStaticStringPointer(*pointer));
                 self.emit_node(IRNode::Push64(*len as u64));
@@ -559,38 +744,100 @@ impl<'a> IRGen<'a> {
             },
 
             CTimeVal::DynamicFnDispatcher{ .. } => {
-                self.emit_diagnostic(&SourceLocation::garbage(), "an overload set cannot indirectly coerce to a function pointer");
+                self.emit_diagnostic(span, "OverloadSetCoercion", "an overload set cannot indirectly coerce to a function pointer");
             },
 
             CTimeVal::Namespace(..) => {
-                self.emit_diagnostic(&SourceLocation::garbage(), "cannot generate a namespace as an expression");
+                self.emit_diagnostic(span, "NamespaceAsValue", "cannot generate a namespace as an expression");
             },
 
             CTimeVal::Type(..) => {
-                self.emit_diagnostic(&SourceLocation::garbage(), "cannot generate a type as a const value");
+                self.emit_diagnostic(span, "TypeAsValue", "cannot generate a type as a const value");
             },
         }
     }
 
     fn gen_expr(&mut self, expr: &'a Expr) {
         match expr.as_enum() {
-            ExprEnum::TypeUInt64 => {
-                self.emit_diagnostic(expr.get_loc(), "add compile errors (type `u64` is not allowed as a generatable expression)");
+            ExprEnum::TypeInt(kind) => {
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", format!("type `{kind}` cannot be used as a value").as_str());
             },
 
             ExprEnum::TypeString => {
-                self.emit_diagnostic(expr.get_loc(), "add compile errors (type `str` is not allowed as a generatable expression)");
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", "type `str` cannot be used as a value");
+            },
+
+            ExprEnum::TypeBool => {
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", "type `bool` cannot be used as a value");
+            },
+
+            ExprEnum::TypeChar => {
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", "type `char` cannot be used as a value");
+            },
+
+            ExprEnum::TypeArray(..) | ExprEnum::TypeSlice(..) | ExprEnum::TypeNullable(..) | ExprEnum::TypeSelf => {
+                let typeval = self.resolve_expr(expr).typeval;
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", format!("type `{typeval}` cannot be used as a value").as_str());
             },
 
             ExprEnum::TypeUnit => {
                 // nothing to generate
             },
+
+            ExprEnum::TypeFunction(..) => {
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", "a function type cannot be used as a value");
+            },
             
             ExprEnum::IntLit(int) => {
                 self.emit_node(IRNode::Push64(*int));
                 self.stack_sz += SIZE_64;
             },
 
+            ExprEnum::BoolLit(boolean) => {
+                self.emit_node(IRNode::Push64(*boolean as u64));
+                self.stack_sz += SIZE_64;
+            },
+
+            ExprEnum::CharLit(byte) => {
+                self.emit_node(IRNode::Push64(*byte as u64));
+                self.stack_sz += SIZE_64;
+            },
+
+            ExprEnum::NullLit => {
+                self.emit_node(IRNode::Push64(0));
+                self.stack_sz += SIZE_64;
+            },
+
+            ExprEnum::ArrayLit(elements) => {
+                let typeval = self.resolve_expr(expr).typeval;
+                let TypeValEnum::Array(element_typeval, len) = typeval.as_enum() else {
+                    unreachable!() // declarations only hand down array types
+                };
+
+                if elements.len() != *len {
+                    self.emit_diagnostic(expr.get_span(), "ArrayLengthMismatch", format!("expected {len} elements for `{typeval}`, but got {}", elements.len()).as_str());
+                }
+
+                // every element is written to its own slot, the first one is on top (at the lowest address)
+                let element_size = element_typeval.stack_size_of();
+                self.emit_node(IRNode::StackAlloc(typeval.stack_size_of()));
+                self.stack_sz += typeval.stack_size_of();
+                for (i, element) in elements.iter().enumerate().take(*len) {
+                    let symbol = self.resolve_expr(element);
+                    if !Self::converts_implicitly(&symbol, element_typeval) {
+                        if Self::byte_kind(&symbol.typeval).is_some() && Self::byte_kind(element_typeval).is_some() {
+                            self.check_int_conversion(&symbol, element_typeval, element.get_span());
+                        } else {
+                            self.emit_diagnostic(element.get_span(), "ElementTypeMismatch", format!("expected `{element_typeval}` for element {i}, but got `{}`", symbol.typeval).as_str());
+                        }
+                        continue
+                    }
+
+                    self.gen_expr(element);
+                    self.pop_to_stack(element_typeval, element_size * (i + 1));
+                }
+            },
+
             ExprEnum::StringLit(string) => {
                 let static_string_pointer = self.new_static_string(string);
                 self.emit_node(IRNode::PushStaticStringPointer(static_string_pointer));
@@ -603,21 +850,128 @@ impl<'a> IRGen<'a> {
                 if let Some(const_val) = symbol.const_val {
                     match const_val {
                         CTimeVal::Type(_) => {
-                            self.emit_diagnostic(expr.get_loc(), "unable to dereference a type");
+                            self.emit_diagnostic(expr.get_span(), "DerefType", "unable to dereference a type");
+                            self.gen_poison(expr);
                         },
                         
                         _ => {
                             // cannot deref to a ctimeval
-                            self.emit_diagnostic(expr.get_loc(), "cannot dereference to a compile time constant");
+                            self.emit_diagnostic(expr.get_span(), "DerefConstant", "cannot dereference to a compile time constant");
                         },
                     }
+                } else if let TypeValEnum::NullablePointer(..) = symbol.typeval.as_enum() {
+                    self.emit_deref_nullable(&symbol.typeval, expr.get_span());
+                    self.gen_poison(expr);
                 } else if !symbol.typeval.is_ptr() {
-                    self.emit_diagnostic(expr.get_loc(), "dereference on a non-pointer type");
+                    self.emit_diagnostic(expr.get_span(), "DerefNonPointer", "dereference on a non-pointer type");
+                    self.gen_poison(expr);
                 } else {
+                    let size = symbol.typeval.to_lessptr().stack_size_of();
                     self.gen_expr(subexpr);
-                    self.stack_sz -= symbol.typeval.size_of();
-                    self.emit_node(IRNode::Deref64);
-                    self.stack_sz += SIZE_64;
+                    self.stack_sz -= SIZE_64;
+                    self.emit_node(if size == SIZE_64 { IRNode::Deref64 } else { IRNode::Deref(size) });
+                    self.stack_sz += size;
+                }
+            },
+
+            ExprEnum::Negate(subexpr) => {
+                let symbol = self.resolve_expr(expr);
+                if let Some(const_val) = symbol.const_val {
+                    self.gen_const_val(&const_val, expr.get_span());
+                } else {
+                    match symbol.typeval.as_enum() {
+                        TypeValEnum::Int(kind) if kind.is_signed() => {
+                            self.check_const_overflow(expr);
+                            self.gen_expr(subexpr);
+                            self.emit_node(IRNode::Neg64);
+                            self.gen_truncate(*kind);
+                        },
+
+                        TypeValEnum::Int(kind) => {
+                            self.emit(
+                                Diagnostic::error(*expr.get_span(), "InvalidNegation", format!("cannot negate unsigned `{kind}`").as_str())
+                                    .with_note("convert it to a signed type first, like `x as i64`")
+                            );
+                            self.gen_poison(expr);
+                        },
+
+                        _ => {
+                            self.emit_diagnostic(expr.get_span(), "InvalidNegation", format!("cannot negate `{}`", symbol.typeval).as_str());
+                            self.gen_poison(expr);
+                        },
+                    }
+                }
+            },
+
+            ExprEnum::BitNot(subexpr) => {
+                let symbol = self.resolve_expr(expr);
+                if let Some(const_val) = symbol.const_val {
+                    self.gen_const_val(&const_val, expr.get_span());
+                } else if let TypeValEnum::Int(kind) = symbol.typeval.as_enum() {
+                    self.gen_expr(subexpr);
+                    self.emit_node(IRNode::Not64);
+                    self.gen_truncate(*kind); // the bits above an unsigned kind must stay clear
+                } else {
+                    self.emit_diagnostic(expr.get_span(), "InvalidBitNot", format!("cannot invert the bits of `{}`", symbol.typeval).as_str());
+                    self.gen_poison(expr);
+                }
+            },
+
+            ExprEnum::Not(subexpr) => {
+                self.check_condition(subexpr);
+                if let Some(const_val) = self.resolve_expr(expr).const_val {
+                    self.gen_const_val(&const_val, expr.get_span());
+                } else {
+                    // `x == false`, which the optimizer can fuse with a following branch
+                    self.gen_expr(subexpr);
+                    self.emit_node(IRNode::Push64(0));
+                    self.emit_node(IRNode::Cmp64(Condition::Eq));
+                }
+            },
+
+            ExprEnum::Cast(subexpr, _) => {
+                let from_typeval = self.resolve_expr(subexpr).typeval;
+                let symbol = self.resolve_expr(expr);
+
+                match (from_typeval.as_enum(), symbol.typeval.as_enum()) {
+                    (TypeValEnum::Int(..) | TypeValEnum::Bool | TypeValEnum::Char, TypeValEnum::Int(..))
+                    | (TypeValEnum::Int(..) | TypeValEnum::Char, TypeValEnum::Char) => {
+                        if let Some(const_val) = symbol.const_val {
+                            self.gen_const_val(&const_val, expr.get_span());
+                        } else {
+                            self.gen_expr(subexpr);
+
+                            // a `char` is a byte, like `u8`, and `bool` is already 0 or 1, which fits every kind
+                            let kind = Self::byte_kind(&symbol.typeval).unwrap_or(IntKind::U8);
+                            if Self::byte_kind(&from_typeval).is_some_and(|from_kind| !from_kind.widens_to(kind)) {
+                                self.gen_truncate(kind);
+                            }
+                        }
+                    },
+
+                    _ if from_typeval == symbol.typeval => self.gen_expr(subexpr),
+
+                    // addresses are plain words, reinterpreting them is up to the programmer
+                    (TypeValEnum::Pointer(..) | TypeValEnum::NullablePointer(..), TypeValEnum::Pointer(..) | TypeValEnum::NullablePointer(..) | TypeValEnum::Int(IntKind::U64))
+                    | (TypeValEnum::Int(IntKind::U64), TypeValEnum::Pointer(..) | TypeValEnum::NullablePointer(..)) => {
+                        if !self.is_unsafe_allowed() {
+                            self.emit_diagnostic(expr.get_span(), "UnsafeRequired", "pointer casts are only allowed in `unsafe` blocks");
+                        }
+                        self.gen_expr(subexpr);
+                    },
+
+                    _ => {
+                        let note = if from_typeval.is_ptr() || symbol.typeval.is_ptr() {
+                            "pointers can only be converted to other pointers and `u64`"
+                        } else {
+                            "only integers, `char` and `bool` can be converted with `as`"
+                        };
+                        self.emit(
+                            Diagnostic::error(*expr.get_span(), "InvalidCast", format!("cannot cast `{from_typeval}` to `{}`", symbol.typeval).as_str())
+                                .with_note(note)
+                        );
+                        self.gen_poison(expr);
+                    },
                 }
             },
             
@@ -626,66 +980,157 @@ impl<'a> IRGen<'a> {
                 // ref to
                 if let Some(var) = symbol.var {
                     // ref to
-                    if let Some(_global_pos) = var.global_pos {
-                        todo!()
-                    } else if let Some(stack_loc) = var.stack_loc {
-                        self.emit_node(IRNode::PushStackPointer(self.stack_sz - stack_loc));
-                        self.stack_sz += SIZE_64;
+                    if !self.gen_var_address(&var) {
+                        self.emit_diagnostic(subexpr.get_span(), "RefNonVariable", format!("cannot grab a reference to `{}`, it has no storage", var.name).as_str());
+                        self.gen_poison(expr);
+                    }
+                } else if let ExprEnum::Index(value, index) = subexpr.as_enum()
+                    && Self::is_element_in_place(&self.resolve_expr(value)) {
+                    // an element picked at runtime, its address is found like for a read
+                    if let Some(residue) = self.gen_element_address(value, index, subexpr.get_span()) {
+                        self.gen_squash(0, SIZE_64, residue);
                     } else {
-                        unreachable!()
+                        self.gen_poison(expr);
                     }
                 } else if let Some(const_val) = symbol.const_val {
                     match const_val {
                         _ => {
                             // cannot grab a reference to a ctimeval
-                            self.emit_diagnostic(subexpr.get_loc(), "cannot grab a reference to a compile time constant");
+                            self.emit_diagnostic(subexpr.get_span(), "RefConstant", "cannot grab a reference to a compile time constant");
+                            self.gen_poison(expr);
                         },
                     }
                 } else {
-                    self.emit_diagnostic(expr.get_loc(), "expected a variable to grab a reference to");
+                    self.emit_diagnostic(expr.get_span(), "RefNonVariable", "expected a variable to grab a reference to");
+                    self.gen_poison(expr);
+                }
+            },
+
+            ExprEnum::Call(function, args) => {
+                // arguments are checked while resolving
+                self.resolve_expr(expr);
+                self.gen_call_expr(function, args);
+            },
+
+            ExprEnum::Index(value, index) => {
+                let symbol = self.resolve_expr(expr);
+                if let Some(var) = symbol.var {
+                    // a constant index into an array variable
+                    self.gen_var_read(var, expr.get_span());
+                    return
                 }
+
+                let Some(residue) = self.gen_element_address(value, index, expr.get_span()) else {
+                    self.gen_poison(expr);
+                    return
+                };
+
+                let size = symbol.typeval.stack_size_of();
+                let value_typeval = self.resolve_expr(value).typeval;
+                self.emit_node(match value_typeval.as_enum() {
+                    TypeValEnum::StringSlice => IRNode::Deref8,
+                    _ if size == SIZE_64 => IRNode::Deref64,
+                    _ => IRNode::Deref(size),
+                });
+                self.stack_sz = self.stack_sz - SIZE_64 + size;
+                self.gen_squash(0, size, residue);
             },
 
-            ExprEnum::Call(expr, args) => self.gen_call_expr(expr, args),
+            ExprEnum::Slice(value, start, end) => self.gen_slice(expr, value, start.as_deref(), end.as_deref()),
 
             ExprEnum::Variable(name) => {
                 let var = self.lookup_var(name).cloned();
                 if let Some(var) = var {
-                    self.gen_var_read(var);
+                    self.gen_var_read(var, expr.get_span());
+                } else if self.is_enclosing_local(name) {
+                    self.emit_diagnostic(expr.get_span(), "NotCaptured", format!("`{name}` belongs to an enclosing function, capture it with `{{{name}}}` or `{{&{name}}}`").as_str());
                 } else {
-                    self.emit_diagnostic(expr.get_loc(), "variable not found");
+                    self.emit_diagnostic(expr.get_span(), "VariableNotFound", "variable not found");
                 }
             },
 
-            ExprEnum::MemberAccess(expr, name) => {
+            ExprEnum::MemberAccess(object, name) => {
+                // missing members are reported while resolving
                 let symbol = self.resolve_expr(expr);
-                if let Some(const_val) = symbol.const_val {
-                    match const_val {
-                        CTimeVal::Namespace(map) => {
-                            let var = map.get(name);
-                            if let Some(var) = var {
-                                let typeval = var.typeval.clone();
-
-                                if let Some(const_val) = var.const_val.clone() {
-                                    self.gen_const_val(&const_val);
-                                } else if let Some(external) = var.external.clone() {
-                                    self.external_read_push(&typeval, external.clone());
-                                } else {
-                                    if let Some(pos) = var.global_pos {
-                                        self.global_read_push(&typeval, pos);
-                                    } else if let Some(stack_loc) = var.stack_loc {
-                                        self.stack_read_push(&typeval, self.stack_sz - stack_loc);
-                                    }
-                                }
+                if let Some(var) = symbol.var {
+                    self.gen_var_read(var, expr.get_span());
+                } else if let Some(const_val) = symbol.const_val {
+                    self.gen_const_val(&const_val, expr.get_span());
+                } else {
+                    match self.resolve_expr(object).typeval.as_enum() {
+                        TypeValEnum::Class(class) => {
+                            // field of a temporary, generate all of it and only keep the field
+                            if let Some((offset, field_typeval)) = class.field(name) {
+                                let field_size = field_typeval.stack_size_of();
+                                self.gen_expr(object);
+                                self.gen_squash(class.stack_size_of() - offset - field_size, field_size, offset);
                             } else {
-                                todo!("add compile errors (member not found)")
+                                self.gen_bound_method(expr, object, name);
                             }
                         },
-                        
-                        _ => todo!("add compile errors (expected a namespace to access)"),
+
+                        TypeValEnum::Pointer(..) => self.gen_bound_method(expr, object, name),
+
+                        TypeValEnum::StringSlice | TypeValEnum::Slice(..) if name == "len" => {
+                            // the length is the top word, drop the pointer under it
+                            self.gen_expr(object);
+                            self.gen_squash(0, SIZE_64, SIZE_64);
+                        },
+
+                        _ => {},
+                    }
+                }
+            },
+
+            ExprEnum::Class(..) => {
+                self.emit_diagnostic(expr.get_span(), "TypeAsValue", "a class cannot be used as a value");
+            },
+
+            ExprEnum::ClassLit(class_expr, field_inits) => {
+                let typeval = self.resolve_expr(expr).typeval;
+                let TypeValEnum::Class(class) = typeval.as_enum() else {
+                    self.emit_diagnostic(class_expr.get_span(), "NotAClass", "expected a class to initialize");
+                    return
+                };
+
+                let mut inits: HashMap<&String, &'a Expr> = HashMap::new();
+                for field_init in field_inits {
+                    match field_init.as_enum() {
+                        StmtEnum::VarDecl(field_name, Some(init), ..) => {
+                            if class.field(field_name).is_none() {
+                                self.emit_diagnostic(field_init.get_span(), "UnknownField", format!("no field named `{field_name}` in class `{typeval}`").as_str());
+                            } else if inits.contains_key(field_name) {
+                                self.emit_diagnostic(field_init.get_span(), "DuplicateField", format!("field `{field_name}` is initialized more than once").as_str());
+                            } else {
+                                inits.insert(field_name, init);
+                            }
+                        },
+                        _ => unreachable!(),
+                    }
+                }
+
+                // always in declaration order, that is the layout
+                for (field_name, field_typeval) in &class.fields {
+                    let Some(init) = inits.get(field_name) else {
+                        self.emit_diagnostic(expr.get_span(), "MissingField", format!("missing field `{field_name}` in `{typeval}`").as_str());
+                        self.emit_node(IRNode::StackAlloc(field_typeval.stack_size_of()));
+                        self.stack_sz += field_typeval.stack_size_of();
+                        continue
+                    };
+
+                    let init_symbol = self.resolve_expr(init);
+                    let init_typeval = init_symbol.typeval.clone();
+                    if !Self::converts_implicitly(&init_symbol, field_typeval) {
+                        if Self::byte_kind(&init_typeval).is_some() && Self::byte_kind(field_typeval).is_some() {
+                            self.check_int_conversion(&init_symbol, field_typeval, init.get_span());
+                        } else {
+                            self.emit_diagnostic(init.get_span(), "FieldTypeMismatch", format!("expected `{field_typeval}` for field `{field_name}`, but got `{init_typeval}`").as_str());
+                        }
+                        self.emit_node(IRNode::StackAlloc(field_typeval.stack_size_of()));
+                        self.stack_sz += field_typeval.stack_size_of();
+                    } else {
+                        self.gen_expr(init);
                     }
-                } else {
-                    todo!("add compile errors (not a constant value to access)")
                 }
             },
 
@@ -694,7 +1139,7 @@ impl<'a> IRGen<'a> {
 
                 match &(**if_kind) {
                     IfKind::Conditional(condition) => {
-                        // let condition_symbol = self.resolve_expr(&condition);
+                        self.check_condition(condition);
                         self.gen_expr(condition);
                         self.emit_node(IRNode::JumpIfNot64FromOffset(0));
                         self.stack_sz -= SIZE_64;
@@ -710,6 +1155,7 @@ impl<'a> IRGen<'a> {
                                     typeval: typeval.clone(),
                                     global_pos: None,
                                     stack_loc: None,
+                                    indirect: None,
                                     const_val: None,
                                     external: None,
                                     is_alias: false,
@@ -728,13 +1174,40 @@ impl<'a> IRGen<'a> {
                                 if let Some(index) = index {
                                     self.emit_node(IRNode::JumpIfNotEqConst64FromOffset(index as u64, 0));
                                 } else {
-                                    self.emit_diagnostic(type_expr.get_loc(), format!("type `{typeval}` is not a variant in tagged union `{}`", init_symbol.typeval).as_str());
+                                    self.emit_diagnostic(type_expr.get_span(), "NotAVariant", format!("type `{typeval}` is not a variant in tagged union `{}`", init_symbol.typeval).as_str());
                                     self.emit_node(IRNode::JumpIfNotEqConst64FromOffset(0, 0));
                                 }
                             },
 
+                            // `name` is the pointer inside the body, which only runs if it is not null
+                            TypeValEnum::NullablePointer(sub_typeval) => {
+                                let typeval = self.resolve_expr(type_expr).typeval;
+                                let pointer_typeval = sub_typeval.clone().to_ptr();
+                                if typeval != pointer_typeval {
+                                    self.emit_diagnostic(type_expr.get_span(), "NotAVariant", format!("expected `{pointer_typeval}` to unwrap `{}`, but got `{typeval}`", init_symbol.typeval).as_str());
+                                }
+
+                                self.gen_expr(init);
+                                let var = Variable {
+                                    name: name.clone(),
+                                    typeval: pointer_typeval,
+                                    global_pos: None,
+                                    stack_loc: Some(self.stack_sz),
+                                    indirect: None,
+                                    const_val: None,
+                                    external: None,
+                                    is_alias: false,
+                                    lifetime: self.lifetime_here(),
+                                    is_unsafe: init_symbol.is_unsafe,
+                                };
+                                self.add_var(var);
+
+                                self.emit_node(IRNode::StackReadPush64(0));
+                                self.emit_node(IRNode::JumpIfNot64FromOffset(0));
+                            },
+
                             _ => {
-                                self.emit_diagnostic(init.get_loc(), format!("type `{}` does not support if bindings", init_symbol.typeval).as_str());
+                                self.emit_diagnostic(init.get_span(), "UnsupportedIfBinding", format!("type `{}` does not support if bindings", init_symbol.typeval).as_str());
                                 self.close_scope();
                                 return
                             },
@@ -744,93 +1217,195 @@ impl<'a> IRGen<'a> {
 
                 // TODO: make 8 bytes not hardcoded
                 let jump_ifn_label = self.cprog.ir_pos();
+                let prev_stack_sz = self.stack_sz;
 
-                self.gen_block(body, false, expr.get_loc());
+                self.gen_block(body, false, expr.get_span());
                 let skipover_else_label = self.cprog.count_ir();
                 if else_body.is_some() {
                     self.emit_node(IRNode::JumpFromOffset(0));
                 }
 
-                let else_label = self.cprog.count_ir() as i64;
-                match self.cprog.node_mut_at(jump_ifn_label) {
-                    IRNode::JumpIfNot64FromOffset(offset) => {
-                        *offset = else_label - (jump_ifn_label as i64);
-                    },
-                    IRNode::JumpIfNotEqConst64FromOffset(_,offset) => {
-                        *offset = else_label - (jump_ifn_label as i64);
-                    },
-                    _ => unreachable!(),
-                }
+                let else_label = self.emit_label();
+                self.patch_jump(jump_ifn_label, else_label);
 
                 if let Some(else_body) = else_body {
-                    self.gen_block(else_body, false, expr.get_loc());
+                    // only one of the branches runs, both start from the same stack
+                    self.stack_sz = prev_stack_sz;
+                    self.gen_block(else_body, false, expr.get_span());
 
-                    let after_else_label = self.cprog.ir_pos() as i64;
-                    match self.cprog.node_mut_at(skipover_else_label) {
-                        IRNode::JumpFromOffset(offset) => {
-                            *offset = after_else_label - (skipover_else_label as i64);
-                        },
-                        _ => unreachable!(),
-                    }
+                    let after_else_label = self.emit_label();
+                    self.patch_jump(skipover_else_label, after_else_label);
                 }
 
                 self.close_scope();
             },
 
-            ExprEnum::Block(block, is_unsafe_block) => self.gen_block(block, *is_unsafe_block, expr.get_loc()),
+            ExprEnum::While(condition, body) => {
+                self.gen_loop(Some(condition), body, Some(TypeValEnum::Unit.to_tval()), expr.get_span());
+            },
 
-            ExprEnum::Function(..) => {
-                self.resolve_expr(expr);
+            ExprEnum::Loop(body) => {
+                let typeval = self.resolve_expr(expr).typeval;
+                self.gen_loop(None, body, Some(typeval), expr.get_span());
+            },
+
+            ExprEnum::Select(value, arms) => {
+                let typeval = self.resolve_expr(expr).typeval;
+                self.gen_select(value, arms, Some(typeval), expr.get_span());
+            },
+
+            ExprEnum::Break(value) => {
+                let Some(loop_info) = self.loops.last().cloned() else {
+                    self.emit_diagnostic(expr.get_span(), "BreakOutsideLoop", "`break` outside of a loop");
+                    return
+                };
+
+                let prev_stack_sz = self.stack_sz;
+                let typeval = match value {
+                    Some(value) if loop_info.is_while => {
+                        self.emit_diagnostic(value.get_span(), "BreakValueInWhile", "`break` with a value is only allowed inside `loop`");
+                        TypeValEnum::Unit.to_tval()
+                    },
+
+                    Some(value) => {
+                        let symbol = self.resolve_expr(value);
+                        if let Some(other_lifetime) = symbol.lifetime
+                            && other_lifetime < loop_info.lifetime {
+                            self.emit_diagnostic(value.get_span(), "LifetimeTooShort", "lifetime may not live long enough to leave the loop");
+                        }
+
+                        self.gen_expr(value);
+                        symbol.typeval
+                    },
+
+                    None => TypeValEnum::Unit.to_tval(),
+                };
+
+                match &loop_info.result {
+                    Some(expected) if *expected != typeval => {
+                        self.emit_diagnostic(expr.get_span(), "BreakTypeMismatch", format!("expected `break` value of type `{expected}`, but got `{typeval}`").as_str());
+                    },
+                    Some(..) => (),
+                    None => self.loops.last_mut().unwrap().result = Some(typeval.clone()),
+                }
+
+                self.gen_unwind(&typeval, loop_info.stack_base);
+                self.emit_node(IRNode::JumpFromOffset(0));
+                let jump_pos = self.cprog.ir_pos();
+                self.loops.last_mut().unwrap().breaks.push(jump_pos);
+
+                // anything after this is unreachable, but it is still generated as if the loop kept going
+                self.stack_sz = prev_stack_sz;
+            },
+
+            ExprEnum::Continue => {
+                let Some(loop_info) = self.loops.last().cloned() else {
+                    self.emit_diagnostic(expr.get_span(), "ContinueOutsideLoop", "`continue` outside of a loop");
+                    return
+                };
+
+                let prev_stack_sz = self.stack_sz;
+                self.gen_unwind(&TypeValEnum::Unit.to_tval(), loop_info.stack_base);
+                self.emit_node(IRNode::JumpFromOffset(0));
+                let jump_pos = self.cprog.ir_pos();
+                self.patch_jump(jump_pos, loop_info.start_label);
+                self.stack_sz = prev_stack_sz;
+            },
+
+            ExprEnum::Block(block, is_unsafe_block) => self.gen_block(block, *is_unsafe_block, expr.get_span()),
+
+            ExprEnum::Function(_, _, _, captures) => {
+                let symbol = self.resolve_expr(expr);
+                if let Some(const_val) = symbol.const_val {
+                    self.gen_const_val(&const_val, expr.get_span());
+                } else if let TypeValEnum::Closure(_, _, capture_types) = symbol.typeval.as_enum() {
+                    self.gen_closure(expr, captures, capture_types);
+                }
             },
 
+            ExprEnum::OverloadSet(..) => {
+                // only reports the coercion error, overload sets only exist at compile time
+                if let Some(const_val) = self.resolve_expr(expr).const_val {
+                    self.gen_const_val(&const_val, expr.get_span());
+                }
+            },
+
+            ExprEnum::CompoundAssign { operands, op } => self.gen_assign(operands, Some(*op)),
+
             ExprEnum::BinaryOp { operands, op } => {
                 let symbol = self.resolve_expr(expr);
 
                 if let Some(const_val) = symbol.const_val {
-                    self.gen_const_val(&const_val);
+                    self.gen_const_val(&const_val, expr.get_span());
                 } else {
 
                     match op {
-                        Operator::Assign => {
-                            let lhs_symbol = self.resolve_expr(&operands.0);
-                            let rhs_symbol = self.resolve_expr(&operands.1);
-                            if let Some(var) = lhs_symbol.var {
-                                if let Some(stack_loc) = var.stack_loc {
-                                    if !var.is_unsafe && rhs_symbol.is_unsafe {
-                                        self.emit_diagnostic(operands.1.get_loc(), "assignment of an unsafe value to a safe variable");
-                                    } else if let Some(other_lifetime) = rhs_symbol.lifetime {
-                                        if other_lifetime < var.lifetime {
-                                            if !var.is_unsafe && self.is_unsafe_allowed() {
-                                                self.emit_diagnostic(operands.0.get_loc(), "variable must be declared in `unsafe` block");
-                                            } else if !var.is_unsafe {
-                                                self.emit_diagnostic(operands.1.get_loc(), "lifetime may not live long enough");
-                                            }
-                                        }
-                                    }
-
-                                    self.gen_expr(&operands.1);
-                                    self.pop_to_stack(&rhs_symbol.typeval, self.stack_sz - stack_loc);
-                                }
-                            }
+                        Operator::Assign => self.gen_assign(operands, None),
+
+                        Operator::And | Operator::Or => self.gen_short_circuit(*op, operands),
+
+                        Operator::Add | Operator::Sub if self.resolve_expr(&operands.0).typeval.is_ptr() => {
+                            self.gen_pointer_arithmetic(expr, *op, operands);
                         },
                         
-                        Operator::Add => {
+                        Operator::Add | Operator::Sub | Operator::Mul
+                        | Operator::BitOr | Operator::BitAnd | Operator::BitXor => {
+                            let kind = self.check_int_operands(operands, expr.get_span());
+                            self.check_const_overflow(expr);
+
                             self.gen_expr(&operands.0);
                             self.gen_expr(&operands.1);
-                            self.emit_node(IRNode::Add64);
-                            self.stack_sz -= SIZE_64;
+                            self.gen_int_op(*op, kind);
                         },
 
-                        Operator::Sub => {
+                        Operator::Div | Operator::Mod => {
+                            if let Some(CTimeVal::Int(0)) = self.resolve_expr(&operands.1).const_val {
+                                self.emit_diagnostic(operands.1.get_span(), "DivisionByZero", "attempt to divide by zero");
+                            }
+
+                            let kind = self.check_int_operands(operands, expr.get_span());
+                            self.check_const_overflow(expr);
+
                             self.gen_expr(&operands.0);
                             self.gen_expr(&operands.1);
-                            self.emit_node(IRNode::Sub64);
-                            self.stack_sz -= SIZE_64;
+                            self.gen_int_op(*op, kind);
                         },
 
-                        Operator::BitOr => {
-                            unimplemented!()
-                        }
+                        Operator::Shl | Operator::Shr => {
+                            let kind = Self::shifted_kind(&self.resolve_expr(&operands.0));
+                            self.check_shift_amount(kind, &operands.1);
+                            self.check_const_overflow(expr);
+
+                            self.gen_expr(&operands.0);
+                            self.gen_expr(&operands.1);
+                            self.gen_int_op(*op, kind);
+                        },
+
+                        Operator::Eq | Operator::NotEq
+                        | Operator::Less | Operator::LessEq
+                        | Operator::Greater | Operator::GreaterEq => {
+                            let lhs_symbol = self.resolve_expr(&operands.0);
+                            let rhs_symbol = self.resolve_expr(&operands.1);
+
+                            // integers of different kinds can still meet at one kind
+                            let int_kind = Self::unify_ints(&lhs_symbol, &rhs_symbol);
+                            if int_kind.is_none()
+                                && let Err(message) = Self::check_comparison(*op, &lhs_symbol.typeval, &rhs_symbol.typeval) {
+                                self.emit_diagnostic(expr.get_span(), "InvalidComparison", message.as_str());
+                                self.gen_poison(expr);
+                                return
+                            }
+
+                            let condition = match int_kind {
+                                Some(kind) if kind.is_signed() => Self::condition_of(*op).to_signed(),
+                                _ => Self::condition_of(*op),
+                            };
+
+                            self.gen_expr(&operands.0);
+                            self.gen_expr(&operands.1);
+                            self.emit_node(IRNode::Cmp64(condition));
+                            self.stack_sz -= SIZE_64;
+                        },
                     }
 
                 }
@@ -838,25 +1413,868 @@ impl<'a> IRGen<'a> {
         }
     }
 
-    fn gen_var_read(&mut self, var: Variable) {
-        let typeval = var.typeval;
+    /// `lhs and rhs` or `lhs or rhs`, the right side only runs if the left one does not decide the result:
+    /// `and` jumps past it to push `false`, `or` pushes `true` and jumps over it
+    fn gen_short_circuit(&mut self, op: Operator, operands: &'a (Expr, Expr)) {
+        self.check_condition(&operands.0);
+        self.check_condition(&operands.1);
+
+        self.gen_expr(&operands.0);
+        let decided_jump = self.cprog.count_ir();
+        self.emit_node(IRNode::JumpIfNot64FromOffset(0));
+        self.stack_sz -= SIZE_64;
+
+        // both paths leave one `bool` in the same slot
+        match op {
+            Operator::And => self.gen_expr(&operands.1),
+            _ => {
+                self.emit_node(IRNode::Push64(1));
+                self.stack_sz += SIZE_64;
+            },
+        }
+        let end_jump = self.cprog.count_ir();
+        self.emit_node(IRNode::JumpFromOffset(0));
+        self.stack_sz -= SIZE_64;
+
+        let decided_label = self.emit_label();
+        self.patch_jump(decided_jump, decided_label);
+        match op {
+            Operator::And => {
+                self.emit_node(IRNode::Push64(0));
+                self.stack_sz += SIZE_64;
+            },
+            _ => self.gen_expr(&operands.1),
+        }
+
+        let end_label = self.emit_label();
+        self.patch_jump(end_jump, end_label);
+    }
+
+    /// Array literals take the element type of the place they are stored in
+    fn hand_down_array_type(&mut self, value: &'a Expr, typeval: &TypeVal) {
+        if let ExprEnum::ArrayLit(..) = value.as_enum()
+            && let TypeValEnum::Array(..) = typeval.as_enum()
+            && !self.symbol_cache.contains_key(&(value as *const Expr)) {
+            let symbol = CmplSymbol { typeval: typeval.clone(), ..self.resolve_expr(value) };
+            self.symbol_cache.insert(value as *const Expr, symbol);
+        }
+    }
+
+    /// `target = value`, or `target op= value` if `op` is given, into a variable, an element or through a pointer
+    fn gen_assign(&mut self, operands: &'a (Expr, Expr), op: Option<Operator>) {
+        let lhs_symbol = self.resolve_expr(&operands.0);
+        if op.is_none() {
+            self.hand_down_array_type(&operands.1, &lhs_symbol.typeval);
+        }
+        let rhs_symbol = self.resolve_expr(&operands.1);
+
+        // constants are folded into every read, a write would never be seen
+        if let Some(var) = &lhs_symbol.var
+            && (var.const_val.is_some() || var.external.as_ref().is_some_and(|external| external.is_const)) {
+            self.emit_diagnostic(operands.0.get_span(), "AssignToConst", format!("cannot assign to `{}`, it is a constant", var.name).as_str());
+            return
+        }
+
+        let update = match op {
+            Some(op) => match self.check_compound_operands(op, &lhs_symbol, &rhs_symbol, operands) {
+                Some(kind) => Some((op, kind)),
+                None => return,
+            },
+            None => None,
+        };
+
+        if let Some(var) = lhs_symbol.var {
+            // `op=` already checked its operands
+            if update.is_none() && !self.check_assign_type(&rhs_symbol, &var.typeval, operands.1.get_span()) {
+                return
+            }
+
+            if var.stack_loc.is_some() || var.global_pos.is_some() {
+                self.check_assignment_lifetime(&var, &rhs_symbol, operands);
+
+                let place = var.clone();
+                if let Some((op, kind)) = update {
+                    self.gen_var_read(var, operands.0.get_span());
+                    self.gen_expr(&operands.1);
+                    self.gen_int_op(op, kind);
+                } else {
+                    self.gen_expr(&operands.1);
+                }
+
+                if let Some(global_pos) = place.global_pos {
+                    self.pop_to_global(&place.typeval, global_pos);
+                } else if let Some(stack_loc) = place.stack_loc {
+                    self.pop_to_stack(&place.typeval, self.stack_sz - stack_loc);
+                }
+            } else if let Some(external) = &var.external {
+                self.emit_diagnostic(operands.0.get_span(), "AssignToImport", format!("cannot assign to `{}`, it belongs to package `{}`", var.name, external.package_name).as_str());
+            } else if var.indirect.is_some() {
+                // a variable captured by reference is written through its pointer
+                self.check_assignment_lifetime(&var, &rhs_symbol, operands);
+
+                let size = var.typeval.stack_size_of();
+                self.gen_var_address(&var);
+                self.gen_stored_value(&operands.1, update);
+                self.emit_node(IRNode::PopToAddress(size));
+                self.stack_sz -= size + SIZE_64;
+            }
+        } else if let ExprEnum::Index(value, index) = operands.0.as_enum() {
+            let value_symbol = self.resolve_expr(value);
+            if value_symbol.typeval.t_enum == TypeValEnum::StringSlice {
+                self.emit_diagnostic(operands.0.get_span(), "AssignToStr", "cannot assign to a byte of a `str`, string data is read only");
+                return
+            }
+
+            if update.is_none() && !self.check_assign_type(&rhs_symbol, &lhs_symbol.typeval, operands.1.get_span()) {
+                return
+            }
+
+            // the elements share the lifetime of the array or slice
+            if let Some(var) = &value_symbol.var {
+                self.check_assignment_lifetime(var, &rhs_symbol, operands);
+            }
+
+            if let Some(residue) = self.gen_element_address(value, index, operands.0.get_span()) {
+                let size = lhs_symbol.typeval.stack_size_of();
+                self.gen_stored_value(&operands.1, update);
+                self.emit_node(IRNode::PopToAddress(size));
+                self.stack_sz -= size + SIZE_64;
+                self.gen_squash(0, 0, residue);
+            }
+        } else if let ExprEnum::Dereference(pointer) = operands.0.as_enum() {
+            self.gen_pointer_write(pointer, &lhs_symbol, &rhs_symbol, operands, update);
+        }
+    }
+
+    /// Only integers can be updated in place, the kind of the target is kept
+    fn check_compound_operands(&mut self, op: Operator, lhs_symbol: &CmplSymbol, rhs_symbol: &CmplSymbol, operands: &'a (Expr, Expr)) -> Option<IntKind> {
+        let TypeValEnum::Int(kind) = lhs_symbol.typeval.as_enum() else {
+            self.emit_diagnostic(operands.0.get_span(), "InvalidCompoundAssign", format!("`{op}=` needs an integer to update, but got `{}`", lhs_symbol.typeval).as_str());
+            return None
+        };
+
+        match rhs_symbol.typeval.as_enum() {
+            // the shift amount can be of any kind
+            TypeValEnum::Int(..) if matches!(op, Operator::Shl | Operator::Shr) => self.check_shift_amount(*kind, &operands.1),
+            TypeValEnum::Int(..) => {
+                self.check_int_conversion(rhs_symbol, &lhs_symbol.typeval, operands.1.get_span());
+                if matches!(op, Operator::Div | Operator::Mod) && let Some(CTimeVal::Int(0)) = rhs_symbol.const_val {
+                    self.emit_diagnostic(operands.1.get_span(), "DivisionByZero", "attempt to divide by zero");
+                }
+            },
+            _ => {
+                self.emit_diagnostic(operands.1.get_span(), "InvalidCompoundAssign", format!("`{op}=` needs an integer operand, but got `{}`", rhs_symbol.typeval).as_str());
+                return None
+            },
+        }
+
+        Some(*kind)
+    }
+
+    /// The value written through the address on top of the stack, `op=` combines it with the value already there
+    fn gen_stored_value(&mut self, rhs: &'a Expr, update: Option<(Operator, IntKind)>) {
+        if let Some((op, kind)) = update {
+            self.emit_node(IRNode::StackReadPush64(0));
+            self.emit_node(IRNode::Deref64);
+            self.stack_sz += SIZE_64;
+            self.gen_expr(rhs);
+            self.gen_int_op(op, kind);
+        } else {
+            self.gen_expr(rhs);
+        }
+    }
+
+    /// Combines the two integers on top of the stack, the result stays in the range of `kind`
+    fn gen_int_op(&mut self, op: Operator, kind: IntKind) {
+        self.emit_node(match (op, kind.is_signed()) {
+            (Operator::Add, _) => IRNode::Add64,
+            (Operator::Sub, _) => IRNode::Sub64,
+            (Operator::Mul, _) => IRNode::Mul64,
+            (Operator::Div, false) => IRNode::Div64,
+            (Operator::Div, true) => IRNode::IDiv64,
+            (Operator::Mod, false) => IRNode::Mod64,
+            (Operator::Mod, true) => IRNode::IMod64,
+            (Operator::BitOr, _) => IRNode::Or64,
+            (Operator::BitAnd, _) => IRNode::And64,
+            (Operator::BitXor, _) => IRNode::Xor64,
+            (Operator::Shl, _) => IRNode::Shl64,
+            (Operator::Shr, false) => IRNode::Shr64,
+            (Operator::Shr, true) => IRNode::Sar64,
+            _ => unreachable!("`{op}` is not an integer operation"),
+        });
+        self.stack_sz -= SIZE_64;
+
+        // `i8::MIN / -1` and shifts leave the range, the bitwise operations never do
+        if !matches!(op, Operator::BitOr | Operator::BitAnd | Operator::BitXor) {
+            self.gen_truncate(kind);
+        }
+    }
+
+    fn check_assignment_lifetime(&mut self, var: &Variable, rhs_symbol: &CmplSymbol, operands: &'a (Expr, Expr)) {
+        if !var.is_unsafe && rhs_symbol.is_unsafe {
+            self.emit_diagnostic(operands.1.get_span(), "UnsafeAssignment", "assignment of an unsafe value to a safe variable");
+        } else if let Some(other_lifetime) = &rhs_symbol.lifetime
+            && *other_lifetime < var.lifetime {
+            if !var.is_unsafe && self.is_unsafe_allowed() {
+                self.emit_diagnostic(operands.0.get_span(), "UnsafeRequired", "variable must be declared in `unsafe` block");
+            } else if !var.is_unsafe {
+                self.emit_diagnostic(operands.1.get_span(), "LifetimeTooShort", "lifetime may not live long enough");
+            }
+        }
+    }
+
+    /// `*pointer = value`, the value is written with the full width of the pointed to type
+    fn gen_pointer_write(&mut self, pointer: &'a Expr, lhs_symbol: &CmplSymbol, rhs_symbol: &CmplSymbol, operands: &'a (Expr, Expr), update: Option<(Operator, IntKind)>) {
+        if !self.is_unsafe_allowed() {
+            self.emit_diagnostic(operands.0.get_span(), "UnsafeRequired", "writing through a pointer is only allowed in `unsafe` blocks");
+        }
+
+        let pointer_symbol = self.resolve_expr(pointer);
+        if let TypeValEnum::NullablePointer(..) = pointer_symbol.typeval.as_enum() {
+            self.emit_deref_nullable(&pointer_symbol.typeval, operands.0.get_span());
+            return
+        } else if pointer_symbol.const_val.is_some() || !pointer_symbol.typeval.is_ptr() {
+            self.emit_diagnostic(operands.0.get_span(), "DerefNonPointer", "dereference on a non-pointer type");
+            return
+        }
+
+        // `op=` already checked its operands
+        if update.is_none() && !self.check_assign_type(rhs_symbol, &lhs_symbol.typeval, operands.1.get_span()) {
+            return
+        }
+
+        let size = lhs_symbol.typeval.stack_size_of();
+        self.gen_expr(pointer);
+        self.gen_stored_value(&operands.1, update);
+        self.emit_node(IRNode::PopToAddress(size));
+        self.stack_sz -= size + SIZE_64;
+    }
+
+    /// Reports a value that cannot be stored where a `typeval` is, it would not fill the place exactly
+    fn check_assign_type(&mut self, rhs_symbol: &CmplSymbol, typeval: &TypeVal, span: &Span) -> bool {
+        if Self::converts_implicitly(rhs_symbol, typeval) {
+            return true
+        }
+
+        if Self::byte_kind(&rhs_symbol.typeval).is_some() && Self::byte_kind(typeval).is_some() {
+            self.check_int_conversion(rhs_symbol, typeval, span);
+        } else if let (TypeValEnum::NullablePointer(..), TypeValEnum::Pointer(..)) = (rhs_symbol.typeval.as_enum(), typeval.as_enum()) {
+            self.check_null_conversion(rhs_symbol, typeval, span);
+        } else {
+            self.emit_diagnostic(span, "AssignTypeMismatch", format!("expected `{typeval}`, but got `{}`", rhs_symbol.typeval).as_str());
+        }
+        false
+    }
+
+    fn emit_deref_nullable(&mut self, typeval: &TypeVal, span: &Span) {
+        let TypeValEnum::NullablePointer(sub_typeval) = typeval.as_enum() else {
+            unreachable!()
+        };
+        self.emit(
+            Diagnostic::error(*span, "DerefNullable", format!("cannot dereference `{typeval}`, it may be null").as_str())
+                .with_note(format!("check it first with `if (value :: name *{sub_typeval})`").as_str())
+        );
+    }
+
+    /// `pointer ± int` moves by whole elements, `pointer - pointer` counts the elements between them
+    fn gen_pointer_arithmetic(&mut self, expr: &'a Expr, op: Operator, operands: &'a (Expr, Expr)) {
+        let lhs_symbol = self.resolve_expr(&operands.0);
+        let rhs_symbol = self.resolve_expr(&operands.1);
+
+        // `*void` moves by single bytes
+        let stride = lhs_symbol.typeval.clone().to_lessptr().stack_size_of().max(1) as u64;
+
+        if !self.is_unsafe_allowed() {
+            self.emit_diagnostic(expr.get_span(), "UnsafeRequired", "pointer arithmetic is only allowed in `unsafe` blocks");
+        }
+
+        match rhs_symbol.typeval.as_enum() {
+            TypeValEnum::Int(..) => {
+                self.gen_expr(&operands.0);
+                self.gen_expr(&operands.1);
+                if stride != 1 {
+                    self.emit_node(IRNode::Push64(stride));
+                    self.emit_node(IRNode::Mul64);
+                }
+                self.emit_node(if matches!(op, Operator::Add) { IRNode::Add64 } else { IRNode::Sub64 });
+                self.stack_sz -= SIZE_64;
+            },
+
+            TypeValEnum::Pointer(..) if matches!(op, Operator::Sub) && rhs_symbol.typeval == lhs_symbol.typeval => {
+                self.gen_expr(&operands.0);
+                self.gen_expr(&operands.1);
+                self.emit_node(IRNode::Sub64);
+                if stride != 1 {
+                    self.emit_node(IRNode::Push64(stride));
+                    self.emit_node(IRNode::IDiv64);
+                }
+                self.stack_sz -= SIZE_64;
+            },
+
+            _ => {
+                self.emit(
+                    Diagnostic::error(*expr.get_span(), "InvalidPointerArithmetic", format!("cannot {} `{}` and `{}`", if matches!(op, Operator::Add) { "add" } else { "subtract" }, lhs_symbol.typeval, rhs_symbol.typeval).as_str())
+                        .with_note("pointers move by integers, and only pointers of the same type can be subtracted")
+                );
+                self.gen_poison(expr);
+            },
+        }
+    }
+
+    /// Whether indexing addresses the elements where they are stored, and not in a copy
+    fn is_element_in_place(value_symbol: &CmplSymbol) -> bool {
+        match (&value_symbol.var, value_symbol.typeval.as_enum()) {
+            (_, TypeValEnum::Slice(..)) => true,
+            (Some(var), TypeValEnum::Array(..)) => var.stack_loc.is_some() || var.indirect.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Element size and length (if it is known at compile time) of anything that can be indexed
+    fn indexable_parts(symbol: &CmplSymbol) -> Option<(usize, Option<usize>)> {
+        match symbol.typeval.as_enum() {
+            TypeValEnum::StringSlice => match symbol.const_val {
+                Some(CTimeVal::StringSlice(_, len)) => Some((1, Some(len))),
+                _ => Some((1, None)),
+            },
+            TypeValEnum::Array(element_typeval, len) => Some((element_typeval.stack_size_of(), Some(*len))),
+            TypeValEnum::Slice(element_typeval) => Some((element_typeval.stack_size_of(), None)),
+            _ => None,
+        }
+    }
+
+    /// Reports an index or slice bound that is not an integer, returns its value if it is a constant
+    fn check_index(&mut self, index: &'a Expr) -> Result<Option<i128>, ()> {
+        let symbol = self.resolve_expr(index);
+        if !matches!(symbol.typeval.as_enum(), TypeValEnum::Int(..)) {
+            self.emit_diagnostic(index.get_span(), "InvalidIndex", format!("index must be an integer, but got `{}`", symbol.typeval).as_str());
+            return Err(())
+        }
+
+        self.check_int_conversion(&symbol, &TypeValEnum::Int(IntKind::U64).to_tval(), index.get_span());
+        match symbol.const_val {
+            Some(CTimeVal::Int(int)) => Ok(Some(int)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Leaves the address of `value[index]` on top of whatever had to be generated to reach it,
+    /// returns the size of the latter so it can be dropped once the element was used
+    fn gen_element_address(&mut self, value: &'a Expr, index: &'a Expr, span: &Span) -> Option<usize> {
+        let value_symbol = self.resolve_expr(value);
+        let Some((element_size, len)) = Self::indexable_parts(&value_symbol) else {
+            self.emit_diagnostic(span, "InvalidIndex", format!("cannot index into `{}`", value_symbol.typeval).as_str());
+            return None
+        };
+
+        let const_index = self.check_index(index).ok()?;
+        if let (Some(int), Some(len)) = (const_index, len)
+            && int >= len as i128 {
+            self.emit_diagnostic(index.get_span(), "IndexOutOfBounds", format!("index `{int}` is out of bounds, the length is {len}").as_str());
+        }
+
+        // arrays in variables are indexed in place, anything else is generated first
+        let array_var = match (&value_symbol.var, value_symbol.typeval.as_enum()) {
+            (Some(var), TypeValEnum::Array(..)) if var.stack_loc.is_some() || var.indirect.is_some() => Some(var.clone()),
+            _ => None,
+        };
+        let residue = if array_var.is_some() {
+            0
+        } else {
+            self.gen_expr(value);
+            value_symbol.typeval.stack_size_of()
+        };
+        let value_loc = self.stack_sz;
+
+        self.gen_expr(index);
+
+        // constant indices into arrays are checked at compile time, `unsafe` code is trusted
+        let is_checked = !self.is_unsafe_allowed() && (const_index.is_none() || len.is_none());
+        let is_array = matches!(value_symbol.typeval.as_enum(), TypeValEnum::Array(..));
+        if is_checked {
+            match len {
+                Some(len) if is_array => self.emit_node(IRNode::Push64(len as u64)),
+                _ => self.emit_node(IRNode::StackReadPush64(SIZE_64)), // the length is under the index
+            }
+            self.emit_node(IRNode::IndexCheck64);
+        }
+
+        if element_size != 1 {
+            self.emit_node(IRNode::Push64(element_size as u64));
+            self.emit_node(IRNode::Mul64);
+        }
+
+        if let Some(array_var) = &array_var {
+            self.gen_var_address(array_var);
+            self.stack_sz -= SIZE_64;
+        } else if is_array {
+            self.emit_node(IRNode::PushStackPointer(self.stack_sz - value_loc));
+        } else {
+            self.emit_node(IRNode::StackReadPush64(2 * SIZE_64)); // the pointer is under the length
+        }
+        self.emit_node(IRNode::Add64);
+
+        Some(residue)
+    }
+
+    /// `value[start:end]`, the result points into `value`
+    fn gen_slice(&mut self, expr: &'a Expr, value: &'a Expr, start: Option<&'a Expr>, end: Option<&'a Expr>) {
+        let value_symbol = self.resolve_expr(value);
+        let Some((element_size, len)) = Self::indexable_parts(&value_symbol) else {
+            self.emit_diagnostic(expr.get_span(), "InvalidSlice", format!("cannot slice `{}`", value_symbol.typeval).as_str());
+            self.gen_poison(expr);
+            return
+        };
+
+        let (Ok(const_start), Ok(const_end)) = (
+            start.map_or(Ok(Some(0)), |start| self.check_index(start)),
+            end.map_or(Ok(len.map(|len| len as i128)), |end| self.check_index(end)),
+        ) else {
+            self.gen_poison(expr);
+            return
+        };
+
+        if let (Some(const_start), Some(const_end)) = (const_start, const_end)
+            && const_start > const_end {
+            self.emit_diagnostic(expr.get_span(), "InvalidSlice", format!("slice starts at `{const_start}` but ends at `{const_end}`").as_str());
+        } else if let (Some(const_end), Some(len)) = (const_end, len)
+            && const_end > len as i128 {
+            self.emit_diagnostic(expr.get_span(), "IndexOutOfBounds", format!("slice end `{const_end}` is out of bounds, the length is {len}").as_str());
+        }
+
+        // pointer and length of all of it
+        if let TypeValEnum::Array(..) = value_symbol.typeval.as_enum() {
+            if !value_symbol.var.as_ref().is_some_and(|var| var.global_pos.is_none() && self.gen_var_address(var)) {
+                self.emit_diagnostic(value.get_span(), "RefNonVariable", "cannot slice a temporary array, it has no storage");
+                self.gen_poison(expr);
+                return
+            }
+
+            self.emit_node(IRNode::Push64(len.unwrap_or_default() as u64));
+            self.stack_sz += SIZE_64;
+        } else {
+            self.gen_expr(value);
+        }
+
+        let is_checked = !(self.is_unsafe_allowed() || (const_start.is_some() && const_end.is_some() && len.is_some()));
+
+        // end <= length, then start <= end
+        match end {
+            Some(end) => self.gen_expr(end),
+            None => {
+                self.emit_node(IRNode::StackReadPush64(0));
+                self.stack_sz += SIZE_64;
+            },
+        }
+        if is_checked {
+            self.gen_bound_check();
+        }
+
+        match start {
+            Some(start) => self.gen_expr(start),
+            None => {
+                self.emit_node(IRNode::Push64(0));
+                self.stack_sz += SIZE_64;
+            },
+        }
+        if is_checked {
+            self.gen_bound_check();
+        }
+
+        // pointer, length, end, start
+        self.emit_node(IRNode::StackReadPush64(0));
+        if element_size != 1 {
+            self.emit_node(IRNode::Push64(element_size as u64));
+            self.emit_node(IRNode::Mul64);
+        }
+        self.emit_node(IRNode::StackReadPush64(4 * SIZE_64));
+        self.emit_node(IRNode::Add64);
+
+        self.emit_node(IRNode::StackReadPush64(2 * SIZE_64));
+        self.emit_node(IRNode::StackReadPush64(2 * SIZE_64));
+        self.emit_node(IRNode::Sub64);
+        self.stack_sz += 2 * SIZE_64;
+
+        self.gen_squash(0, 2 * SIZE_64, 4 * SIZE_64);
+    }
+
+    /// Panics unless the top value is at most the one under it
+    fn gen_bound_check(&mut self) {
+        self.emit_node(IRNode::StackReadPush64(SIZE_64));
+        self.emit_node(IRNode::Push64(1));
+        self.emit_node(IRNode::Add64);
+        self.emit_node(IRNode::IndexCheck64);
+    }
+
+    fn check_condition(&mut self, condition: &'a Expr) {
+        let condition_typeval = self.resolve_expr(condition).typeval;
+        if condition_typeval.t_enum != TypeValEnum::Bool {
+            let mut diagnostic = Diagnostic::error(*condition.get_span(), "NonBoolCondition", format!("expected `bool` condition, but got `{condition_typeval}`").as_str());
+            if matches!(condition_typeval.as_enum(), TypeValEnum::Int(_)) {
+                diagnostic = diagnostic.with_note("compare it explicitly instead, like `x != 0`");
+            }
+            self.emit(diagnostic);
+        }
+    }
+
+    /// Generates `while` (with a condition) and `loop` (without one), returns the type of the loop,
+    /// `result` is the expected type of `break` values or `None` to take it from the first `break`
+    fn gen_loop(&mut self, condition: Option<&'a Expr>, body: &'a AstBlock, result: Option<TypeVal>, span: &Span) -> TypeVal {
+        let stack_base = self.stack_sz;
+        let start_label = self.emit_label();
+
+        let exit_jump = if let Some(condition) = condition {
+            self.check_condition(condition);
+            self.gen_expr(condition);
+            self.emit_node(IRNode::JumpIfNot64FromOffset(0));
+            self.stack_sz -= SIZE_64;
+            Some(self.cprog.ir_pos())
+        } else {
+            None
+        };
+
+        let loop_info = LoopInfo::new(start_label, stack_base, result, condition.is_some(), self.lifetime_here());
+        self.loops.push(loop_info);
+
+        // the value of the body is thrown away every iteration
+        self.gen_block(body, false, span);
+        if self.stack_sz > stack_base {
+            self.emit_node(IRNode::StackDealloc(self.stack_sz - stack_base));
+            self.stack_sz = stack_base;
+        }
+
+        self.emit_node(IRNode::JumpFromOffset(0));
+        let jump_back = self.cprog.ir_pos();
+        self.patch_jump(jump_back, start_label);
+
+        let end_label = self.emit_label();
+        let loop_info = self.loops.pop().unwrap();
+        if let Some(exit_jump) = exit_jump {
+            self.patch_jump(exit_jump, end_label);
+        }
+        for break_jump in loop_info.breaks {
+            self.patch_jump(break_jump, end_label);
+        }
+
+        let typeval = loop_info.result.unwrap_or_default();
+        self.stack_sz = stack_base + typeval.stack_size_of();
+        typeval
+    }
+
+    /// Matches every variant of a tagged union, `result` is `None` while the type of the arms is still unknown
+    fn gen_select(&mut self, value: &'a Expr, arms: &'a [SelectArm], result: Option<TypeVal>, span: &Span) -> TypeVal {
+        let value_symbol = self.resolve_expr(value);
+        let TypeValEnum::TaggedUnion(variants) = value_symbol.typeval.as_enum() else {
+            self.emit_diagnostic(value.get_span(), "UnsupportedSelect", format!("cannot select on type `{}`, expected a tagged union", value_symbol.typeval).as_str());
+            return TypeValEnum::Unit.to_tval()
+        };
+
+        // find the variant of every arm
+        let mut arm_variants: Vec<Option<usize>> = Vec::new();
+        let mut handled = vec![false; variants.len()];
+        let mut else_span = None;
+        for (i, arm) in arms.iter().enumerate() {
+            let variant = match &arm.pattern {
+                SelectPattern::Variant { type_expr, .. } => {
+                    let typeval = self.resolve_expr(type_expr).typeval;
+                    match variants.iter().position(|variant| *variant == typeval) {
+                        Some(index) if handled[index] => {
+                            self.emit_diagnostic(type_expr.get_span(), "DuplicateArm", format!("variant `{typeval}` is already handled").as_str());
+                            None
+                        },
+                        Some(index) => {
+                            handled[index] = true;
+                            Some(index)
+                        },
+                        None => {
+                            self.emit_diagnostic(type_expr.get_span(), "NotAVariant", format!("type `{typeval}` is not a variant in tagged union `{}`", value_symbol.typeval).as_str());
+                            None
+                        },
+                    }
+                },
+
+                SelectPattern::Else => {
+                    if i + 1 != arms.len() {
+                        self.emit_diagnostic(&arm.span, "ElseNotLast", "`else` must be the last arm of a `select`");
+                    }
+                    else_span = Some(arm.span);
+                    None
+                },
+            };
+
+            arm_variants.push(variant);
+        }
+
+        let missing: Vec<String> = variants.iter().zip(&handled)
+            .filter(|(_, is_handled)| !**is_handled)
+            .map(|(variant, _)| format!("`{variant}`"))
+            .collect();
+
+        match else_span {
+            None if !missing.is_empty() => {
+                let diagnostic = Diagnostic::error(*span, "NonExhaustiveSelect", format!("`select` does not handle the variants {} of `{}`", missing.join(", "), value_symbol.typeval).as_str())
+                    .with_note("add the missing arms or an `else` arm");
+                self.emit(diagnostic);
+            },
+            Some(else_span) if missing.is_empty() => {
+                self.emit_warning(&else_span, "UnreachableElse", "every variant is already handled, so `else` is never reached");
+            },
+            _ => (),
+        }
+
+        // the value is kept below the arms so the bindings can point into it
+        let stack_base = self.stack_sz;
+        self.gen_expr(value);
+        let value_loc = self.stack_sz;
+        let value_size = value_symbol.typeval.stack_size_of();
+        let lifetime = self.lifetime_here();
+
+        let mut arm_types: Vec<TypeVal> = Vec::new();
+        let mut end_jumps = Vec::new();
+        let mut skip_jump: Option<usize> = None;
+        for (i, (arm, variant)) in arms.iter().zip(&arm_variants).enumerate() {
+            if let Some(skip_jump) = skip_jump.take() {
+                let arm_label = self.emit_label();
+                self.patch_jump(skip_jump, arm_label);
+            }
+            self.stack_sz = value_loc;
+
+            // the last arm is the only one left when the select is exhaustive, so it needs no check
+            let is_last = i + 1 == arms.len();
+            if let Some(variant) = variant && !(is_last && else_span.is_none() && missing.is_empty()) {
+                self.emit_node(IRNode::StackReadPush64(self.stack_sz - value_loc)); // the tag is on top
+                self.emit_node(IRNode::JumpIfNotEqConst64FromOffset(*variant as u64, 0));
+                skip_jump = Some(self.cprog.ir_pos());
+            }
+
+            self.open_scope();
+            if let SelectPattern::Variant { name, .. } = &arm.pattern {
+                // the binding is the union space right below the tag
+                let binding = Variable {
+                    name: name.clone(),
+                    typeval: variant.map(|variant| variants[variant].clone()).unwrap_or_default(),
+                    global_pos: None,
+                    stack_loc: Some(value_loc - SIZE_64),
+                    indirect: None,
+                    const_val: None,
+                    external: None,
+                    is_alias: true,
+                    lifetime: self.lifetime_here(),
+                    is_unsafe: false,
+                };
+                self.add_var(binding);
+            }
+
+            let arm_symbol = self.resolve_expr(&arm.body);
+            if let Some(arm_lifetime) = &arm_symbol.lifetime
+                && *arm_lifetime < lifetime {
+                self.emit_diagnostic(arm.body.get_span(), "LifetimeTooShort", "lifetime may not live long enough to leave the select");
+            }
+
+            match &result {
+                None => self.gen_expr(&arm.body),
+                Some(result) if *result == arm_symbol.typeval => self.gen_expr(&arm.body),
+                Some(result) => self.gen_select_arm_variant(&arm.body, &arm_symbol.typeval, result),
+            }
+            self.close_scope();
+            arm_types.push(arm_symbol.typeval);
+
+            if !is_last {
+                self.emit_node(IRNode::JumpFromOffset(0));
+                end_jumps.push(self.cprog.ir_pos());
+            }
+        }
+
+        let end_label = self.emit_label();
+        for end_jump in end_jumps.into_iter().chain(skip_jump) {
+            self.patch_jump(end_jump, end_label);
+        }
+
+        // every arm with a different type becomes a variant of the result
+        let typeval = result.unwrap_or_else(|| {
+            arm_types.into_iter()
+                .reduce(|lhs, rhs| if lhs == rhs { lhs } else { Self::mix_tagged_unions(&lhs, &rhs) })
+                .unwrap_or_default()
+        });
+
+        self.stack_sz = value_loc + typeval.stack_size_of();
+        self.gen_squash(0, typeval.stack_size_of(), value_size);
+        debug_assert_eq!(self.stack_sz, stack_base + typeval.stack_size_of());
+        typeval
+    }
+
+    /// Generates an arm of a `select` as a variant of the tagged union `result`
+    fn gen_select_arm_variant(&mut self, body: &'a Expr, typeval: &TypeVal, result: &TypeVal) {
+        let result_size = result.stack_size_of();
+        let variant = match result.as_enum() {
+            TypeValEnum::TaggedUnion(variants) => variants.iter().position(|variant| variant == typeval),
+            _ => None,
+        };
+
+        let prev_stack_sz = self.stack_sz;
+        if let Some(variant) = variant {
+            let extra_union_space = (result_size - SIZE_64 /*tag*/) - typeval.stack_size_of();
+            self.emit_node(IRNode::StackAlloc(extra_union_space)); // extra unused union space for other variants
+            self.stack_sz += extra_union_space;
+            self.gen_expr(body);
+            self.emit_node(IRNode::Push64(variant as u64));
+            self.stack_sz += SIZE_64;
+        } else {
+            // a tagged union arm would need its tag remapped
+            self.emit_diagnostic(body.get_span(), "SelectArmMismatch", format!("cannot use `{typeval}` as a variant of `{result}`").as_str());
+            self.gen_expr(body);
+            self.emit_node(IRNode::StackDealloc(self.stack_sz - prev_stack_sz));
+            self.emit_node(IRNode::StackAlloc(result_size));
+            self.stack_sz = prev_stack_sz + result_size;
+        }
+    }
+
+    /// Moves the value on top of the stack down to `stack_base` and drops everything
+    /// in between, used to leave nested blocks with `break` and `continue`
+    fn gen_unwind(&mut self, typeval: &TypeVal, stack_base: usize) {
+        let size = typeval.stack_size_of();
+        self.gen_squash(0, size, self.stack_sz - size - stack_base);
+    }
+
+    /// Keeps the `keep` bytes found `above` bytes under the top of the stack,
+    /// drops everything above them and the `below` bytes under them
+    fn gen_squash(&mut self, above: usize, keep: usize, below: usize) {
+        if below > 0 {
+            // bottom word first, the destination is always above the words that are left to copy
+            for word in (0..(keep / SIZE_64)).rev() {
+                let offset = above + word * SIZE_64;
+                self.emit_node(IRNode::StackReadPush64(offset));
+                self.emit_node(IRNode::Pop64ToStack(offset + below + SIZE_64));
+            }
+        }
+
+        if above + below > 0 {
+            self.emit_node(IRNode::StackDealloc(above + below));
+            self.stack_sz -= above + below;
+        }
+    }
+
+    fn gen_var_read(&mut self, var: Variable, span: &Span) {
+        let typeval = var.typeval.clone();
 
         if let Some(const_val) = var.const_val {
-            self.gen_const_val(&const_val);
+            self.gen_const_val(&const_val, span);
         } else if let Some(external) = var.external {
-            self.external_read_push(&typeval, external.clone());
+            self.external_read_push(&typeval, external.clone(), span);
         } else {
             if let Some(pos) = var.global_pos {
                 self.global_read_push(&typeval, pos);
             } else if let Some(stack_loc) = var.stack_loc {
                 self.stack_read_push(&typeval, self.stack_sz - stack_loc);
+            } else if var.indirect.is_some() {
+                self.gen_var_address(&var);
+                self.gen_load(typeval.stack_size_of());
             } else {
-                let size = typeval.size_of();
-                self.emit_node(IRNode::StackAlloc(size));
-                self.stack_sz += size;
-                self.emit_diagnostic(&SourceLocation::garbage(), "variable is invalid and cannot be read");
+                self.emit_node(IRNode::StackAlloc(typeval.stack_size_of()));
+                self.stack_sz += typeval.stack_size_of();
+                self.emit_diagnostic(span, "InvalidVariable", "variable is invalid and cannot be read");
+            }
+        }
+    }
+
+    /// Pushes the captured values (or the addresses of the variables captured by reference), then the function
+    fn gen_closure(&mut self, expr: &'a Expr, captures: &[Capture], capture_types: &[TypeVal]) {
+        for (capture, typeval) in captures.iter().zip(capture_types) {
+            let var = self.lookup_var(&capture.name).cloned();
+            match var {
+                Some(var) if capture.by_ref => {
+                    self.gen_var_address(&var);
+                },
+                Some(var) => self.gen_var_read(var, &capture.span),
+                // already reported while resolving
+                None => {
+                    self.emit_node(IRNode::StackAlloc(typeval.stack_size_of()));
+                    self.stack_sz += typeval.stack_size_of();
+                },
             }
         }
+
+        let address = self.closure_addresses[&(expr as *const Expr)];
+        let offset: i64 = (self.cprog.count_ir() - address).try_into().unwrap();
+        self.emit_node(IRNode::PushAddressFromOffset(-offset));
+        self.stack_sz += ADDRESS_SIZE;
+    }
+
+    /// Pushes the address of the receiver, then the method
+    fn gen_bound_method(&mut self, expr: &'a Expr, object: &'a Expr, name: &str) {
+        let object_symbol = self.resolve_expr(object);
+        let Some(method) = Self::method_class(&object_symbol.typeval)
+            .and_then(|class| self.methods.get(&(class.id, name.to_string())))
+            .cloned() else {
+            return // reported while resolving
+        };
+
+        if let TypeValEnum::Pointer(..) = object_symbol.typeval.as_enum() {
+            self.gen_expr(object);
+        } else if !object_symbol.var.as_ref().is_some_and(|var| self.gen_var_address(var)) {
+            self.emit_diagnostic(object.get_span(), "RefNonVariable", format!("cannot bind `{name}` to a temporary, it has no storage").as_str());
+            self.gen_poison(expr);
+            return
+        }
+
+        if let Some(const_val) = &method.const_val {
+            self.gen_const_val(const_val, expr.get_span());
+        }
+    }
+
+    /// Replaces the address on top of the stack with the `size` bytes it points to
+    fn gen_load(&mut self, size: usize) {
+        self.emit_node(if size == SIZE_64 { IRNode::Deref64 } else { IRNode::Deref(size) });
+        self.stack_sz = self.stack_sz - SIZE_64 + size;
+    }
+
+    /// Pushes where a variable is stored, returns false if it has no storage
+    fn gen_var_address(&mut self, var: &Variable) -> bool {
+        if let Some(global_pos) = var.global_pos {
+            self.emit_node(IRNode::PushGlobalPointer(global_pos));
+        } else if let Some(stack_loc) = var.stack_loc {
+            self.emit_node(IRNode::PushStackPointer(self.stack_sz - stack_loc));
+        } else if let Some((pointer_loc, offset)) = var.indirect {
+            self.emit_node(IRNode::StackReadPush64(self.stack_sz - pointer_loc));
+            if offset != 0 {
+                self.emit_node(IRNode::Push64(offset as u64));
+                self.emit_node(IRNode::Add64);
+            }
+        } else {
+            return false
+        }
+
+        self.stack_sz += SIZE_64;
+        true
+    }
+
+    /// Calls a function without arguments and drops its result
+    fn gen_meta_call(&mut self, symbol: &CmplSymbol, span: &Span) {
+        let TypeValEnum::FunctionPointer(_, return_typeval) = symbol.typeval.as_enum() else {
+            return // no meta function
+        };
+
+        let prev_stack_sz = self.stack_sz;
+        self.emit_node(IRNode::StackAlloc(return_typeval.stack_size_of()));
+        self.stack_sz += return_typeval.stack_size_of();
+
+        if let Some(var) = &symbol.var {
+            self.gen_var_read(var.clone(), span);
+        } else if let Some(const_val) = &symbol.const_val {
+            self.gen_const_val(const_val, span);
+        }
+
+        self.emit_node(IRNode::Call);
+        self.emit_node(IRNode::StackDealloc(return_typeval.stack_size_of()));
+        self.stack_sz = prev_stack_sz;
+    }
+
+    /// Allocates space for the resolved type of an expression that failed to generate,
+    /// this keeps `stack_sz` correct so later errors are still reported properly
+    fn gen_poison(&mut self, expr: &'a Expr) {
+        let size = self.resolve_expr(expr).typeval.stack_size_of();
+        self.emit_node(IRNode::StackAlloc(size));
+        self.stack_sz += size;
     }
 
     fn gen_call_expr(&mut self, expr: &'a Expr, args: &'a Vec<Expr>) {
@@ -866,61 +2284,76 @@ impl<'a> IRGen<'a> {
         let mut queue: VecDeque<(CmplSymbol, bool, bool)> = vec![(init_symbol, false, true)].into();
         // (CmplSymbol, should_drop_result, should_use_args)
 
+        // called right after the args of the selected overload are pushed
+        let mut mid_func: Option<CmplSymbol> = None;
+
         loop {
             if let Some(item) = queue.pop_front() {
                 let (symbol, should_drop_result, should_use_args) = item;
 
                 match symbol.const_val {
-                    Some(CTimeVal::DynamicFnDispatcher { map, meta_funcs }) => {
-                        let first_typeval = self.resolve_expr(args.first().unwrap()).typeval;
-                        let selected = map.get(&first_typeval);
-
-                        let selected_symbol = if let Some(selected) = selected {
-                            selected.clone()
-                        } else {
-                            self.emit_diagnostic(expr.get_loc(), format!("no function overload for type `{first_typeval}`", ).as_str());
-                            CmplSymbol {
-                                const_val: None,
-                                typeval: TypeValEnum::Unit.to_tval(),
-                                var: None,
-                                lifetime: None,
-                                is_unsafe: false,
-                            }
+                    Some(CTimeVal::DynamicFnDispatcher { candidates, meta_funcs }) => {
+                        let matches = self.match_overloads(&candidates, args);
+                        let [selected_symbol] = matches.as_slice() else {
+                            self.report_overload_mismatch(&candidates, &matches, args, expr.get_span());
+                            continue
                         };
-                        
-                        queue.push_back((selected_symbol, false, true));
+
+                        // missing meta functions are `void`
+                        let is_func = |symbol: &CmplSymbol| matches!(symbol.typeval.as_enum(), TypeValEnum::FunctionPointer(..));
+                        let (pre_func, mid, post_func) = *meta_funcs;
+                        if is_func(&pre_func) {
+                            queue.push_back((pre_func, true, false));
+                        }
+                        queue.push_back((selected_symbol.clone(), false, true));
 
                         // run this AFTER
-                        queue.push_back((meta_funcs.2, true, false));
+                        if is_func(&post_func) {
+                            queue.push_back((post_func, true, false));
+                        }
+                        mid_func = Some(mid);
                     }
 
                     _ => match symbol.typeval.as_enum() {
-                        TypeValEnum::FunctionPointer(_param_typevals, return_typeval) => {
+                        TypeValEnum::FunctionPointer(_param_typevals, return_typeval)
+                        | TypeValEnum::Closure(_param_typevals, return_typeval, _)
+                        | TypeValEnum::MethodPointer(_param_typevals, return_typeval) => {
                             // alloc return value
-                            self.emit_node(IRNode::StackAlloc(return_typeval.size_of()));
-                            self.stack_sz += return_typeval.size_of();
+                            self.emit_node(IRNode::StackAlloc(return_typeval.stack_size_of()));
+                            self.stack_sz += return_typeval.stack_size_of();
 
-                            // push args
+                            // push args, `prev_stack_sz` already counts the return value
                             let prev_stack_sz = self.stack_sz;
                             if should_use_args {
                                 for arg in args {
                                     self.gen_expr(arg);
                                 }
+
+                                if let Some(mid_func) = mid_func.take() {
+                                    self.gen_meta_call(&mid_func, expr.get_span());
+                                }
                             }
                             
                             // actually perform the function call
                             if let Some(var) = &symbol.var {
-                                self.gen_var_read(var.clone());
+                                self.gen_var_read(var.clone(), expr.get_span());
+                            } else if let Some(const_val) = &symbol.const_val {
+                                self.gen_const_val(const_val, expr.get_span());
                             } else {
                                 self.gen_expr(expr);
                             }
+
+                            // the captures (or the receiver) stay under the called address, the function drops them with its arguments
+                            if let TypeValEnum::Closure(..) | TypeValEnum::MethodPointer(..) = symbol.typeval.as_enum() {
+                                self.emit_node(IRNode::StackReadPush64(0));
+                            }
                             
                             self.emit_node(IRNode::Call);
                             self.stack_sz = if should_drop_result {
-                                self.emit_node(IRNode::StackDealloc(return_typeval.size_of()));
-                                prev_stack_sz - return_typeval.size_of()
+                                self.emit_node(IRNode::StackDealloc(return_typeval.stack_size_of()));
+                                prev_stack_sz - return_typeval.stack_size_of()
                             } else {
-                                prev_stack_sz + return_typeval.size_of()
+                                prev_stack_sz
                             };
                         },
 
@@ -941,14 +2374,14 @@ impl<'a> IRGen<'a> {
                                 }
                             }
 
-                            let size = typeval.size_of();
+                            let size = typeval.stack_size_of();
                             if should_drop_result {
                                 let prev_stack_sz = self.stack_sz;
                                 self.gen_expr(arg);
                                 self.emit_node(IRNode::StackDealloc(self.stack_sz - prev_stack_sz));
                                 self.stack_sz -= self.stack_sz - prev_stack_sz;
                             } else if let Some(matched_typeval) = matched_typeval {
-                                let extra_union_space = (size - SIZE_64 /*tag*/) - matched_typeval.size_of();
+                                let extra_union_space = (size - SIZE_64 /*tag*/) - matched_typeval.stack_size_of();
                                 self.emit_node(IRNode::StackAlloc(extra_union_space)); // extra unused union space for other variants
                                 self.stack_sz += extra_union_space;
                                 self.gen_expr(arg);
@@ -957,7 +2390,7 @@ impl<'a> IRGen<'a> {
                             } else {
                                 self.emit_node(IRNode::StackAlloc(size));
                                 self.stack_sz += size;
-                                self.emit_diagnostic(expr.get_loc(), format!("no variant `{}` in tagged union `{typeval}`", arg_symbol.typeval).as_str());
+                                self.emit_diagnostic(expr.get_span(), "NotAVariant", format!("no variant `{}` in tagged union `{typeval}`", arg_symbol.typeval).as_str());
                             }
                         },
 
@@ -975,7 +2408,7 @@ impl<'a> IRGen<'a> {
                         },
 
                         _ => {
-                            self.emit_diagnostic(expr.get_loc(), "cannot perform call on this type");
+                            self.emit_diagnostic(expr.get_span(), "NotCallable", "cannot perform call on this type");
                         },
                     }
                 }
@@ -985,12 +2418,12 @@ impl<'a> IRGen<'a> {
         }
     }
 
-    fn gen_block(&mut self, block: &'a AstBlock, is_unsafe_block: bool, loc: &SourceLocation) {
+    fn gen_block(&mut self, block: &'a AstBlock, is_unsafe_block: bool, span: &Span) {
 
         self.emit_node(IRNode::Nop); // ensures that nothing points to the wrong place when the optimizer changes addresses
 
         if is_unsafe_block && self.is_unsafe_allowed() {
-            self.emit_diagnostic(loc, "unnecessary `unsafe` block");
+            self.emit_diagnostic(span, "UnnecessaryUnsafe", "unnecessary `unsafe` block");
         }
         
         // skip block generation if its just a return expr
@@ -1006,11 +2439,11 @@ impl<'a> IRGen<'a> {
         self.open_scope();
         self.unsafe_depth += is_unsafe_block as usize;
 
-        let prev_stack_sz = self.stack_sz;
-
-        // alloc for return (final expr)
-        let stack_alloc_label = self.cprog.count_ir();
-        self.emit_node(IRNode::StackAlloc(0));
+        // alloc for return (final expr), below the locals so they can be dropped after
+        let return_size = self.resolve_block(block).typeval.stack_size_of();
+        self.emit_node(IRNode::StackAlloc(return_size));
+        self.stack_sz += return_size;
+        let return_loc = self.stack_sz;
 
         for stmt in &block.body {
             self.gen_stmt(stmt);
@@ -1018,30 +2451,9 @@ impl<'a> IRGen<'a> {
 
         if let Some(return_expr) = &block.return_expr {
             self.gen_expr(return_expr);
-        }
 
-        if let Some(return_expr) = &block.return_expr {
             let typeval = self.resolve_expr(return_expr).typeval;
-            let return_size = typeval.size_of();
-            let return_typeval = Some(typeval);
-
-            // alloc for return (final expr)
-            match self.cprog.node_mut_at(stack_alloc_label) {
-                IRNode::StackAlloc(size) => {
-                    *size = return_size;
-                },
-                _ => unreachable!(),
-            }
-
-            self.cprog.realign_stack_offsets(stack_alloc_label, self.stack_sz - prev_stack_sz, return_size);
-            self.stack_sz += return_size;
-
-            if let Some(typeval) = return_typeval {
-                match self.scope_locals_stackp() {
-                    Some(locals_begin_stackp) => self.pop_to_stack(&typeval, (self.stack_sz - locals_begin_stackp) + return_size),
-                    None => self.pop_to_stack(&typeval, return_size),
-                }
-            }
+            self.pop_to_stack(&typeval, self.stack_sz - return_loc);
         }
 
         self.close_scope();
@@ -1058,49 +2470,209 @@ impl<'a> IRGen<'a> {
             ExprEnum::Dereference(..) => self.resolve_expr_cached(expr),
             ExprEnum::Reference(..) => self.resolve_expr_cached(expr),
             ExprEnum::Variable(..) => self.resolve_expr_cached(expr),
+            ExprEnum::MemberAccess(..) => self.resolve_expr_cached(expr),
+            ExprEnum::TypeArray(..) => self.resolve_expr_cached(expr),
+            ExprEnum::TypeNullable(..) => self.resolve_expr_cached(expr),
+
+            // declarations may hand down their element type
+            ExprEnum::ArrayLit(..) => self.resolve_expr_cached(expr),
+
+            ExprEnum::OverloadSet(..) => self.resolve_expr_cached(expr),
+
+            // arguments of closures and methods are checked
+            ExprEnum::Call(..) => self.resolve_expr_cached(expr),
+
+            // every declaration is a distinct type
+            ExprEnum::Class(..) => self.resolve_expr_cached(expr),
 
             _ => self.resolve_expr_uncached(expr),
         }
     }
 
-    fn resolve_expr_cached(&mut self, expr: &'a Expr) -> CmplSymbol {
-        if let Some(cached_symbol) = self.symbol_cache.get(&(expr as *const Expr)) {
-            cached_symbol.clone()
-        } else {
-            let symbol = self.resolve_expr_uncached(expr);
-            self.symbol_cache.insert(expr as *const Expr, symbol.clone());
-            symbol
-        }
-    }
-    
-    fn resolve_expr_uncached(&mut self, expr: &'a Expr) -> CmplSymbol {
-        match expr.as_enum() {
-            ExprEnum::TypeUInt64 => CmplSymbol {
-                const_val: Some(CTimeVal::Type(TypeValEnum::UInt64.to_tval())),
-                typeval: TypeValEnum::UInt64.to_tval(),
+    fn resolve_expr_cached(&mut self, expr: &'a Expr) -> CmplSymbol {
+        if let Some(cached_symbol) = self.symbol_cache.get(&(expr as *const Expr)) {
+            cached_symbol.clone()
+        } else {
+            let symbol = self.resolve_expr_uncached(expr);
+            self.symbol_cache.insert(expr as *const Expr, symbol.clone());
+            symbol
+        }
+    }
+    
+    fn resolve_expr_uncached(&mut self, expr: &'a Expr) -> CmplSymbol {
+        match expr.as_enum() {
+            ExprEnum::TypeInt(kind) => CmplSymbol {
+                const_val: Some(CTimeVal::Type(TypeValEnum::Int(*kind).to_tval())),
+                typeval: TypeValEnum::Int(*kind).to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::TypeString => CmplSymbol {
+                const_val: Some(CTimeVal::Type(TypeValEnum::StringSlice.to_tval())),
+                typeval: TypeValEnum::StringSlice.to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::TypeBool => CmplSymbol {
+                const_val: Some(CTimeVal::Type(TypeValEnum::Bool.to_tval())),
+                typeval: TypeValEnum::Bool.to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::TypeChar => CmplSymbol {
+                const_val: Some(CTimeVal::Type(TypeValEnum::Char.to_tval())),
+                typeval: TypeValEnum::Char.to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::TypeSelf => match self.method_classes.last() {
+                Some(typeval) => CmplSymbol {
+                    const_val: Some(CTimeVal::Type(typeval.clone())),
+                    typeval: typeval.clone(),
+                    var: None,
+                    lifetime: None,
+                    is_unsafe: false,
+                },
+                None => {
+                    self.emit_diagnostic(expr.get_span(), "InvalidReceiver", "`self` is only allowed in the methods of a class");
+                    CmplSymbol::void()
+                },
+            },
+
+            ExprEnum::TypeArray(len, element_type) => {
+                let element_typeval = self.resolve_expr(element_type).typeval;
+                let len = match self.resolve_expr(len).const_val {
+                    Some(CTimeVal::Int(int)) if int >= 0 => int as usize,
+                    _ => {
+                        self.emit_diagnostic(len.get_span(), "InvalidArrayLength", "array length must be a non-negative integer constant");
+                        0
+                    },
+                };
+
+                let typeval = TypeValEnum::Array(Box::new(element_typeval), len).to_tval();
+                CmplSymbol {
+                    const_val: Some(CTimeVal::Type(typeval.clone())),
+                    typeval,
+                    var: None,
+                    lifetime: None,
+                    is_unsafe: false,
+                }
+            },
+
+            ExprEnum::TypeSlice(element_type) => {
+                let typeval = TypeValEnum::Slice(Box::new(self.resolve_expr(element_type).typeval)).to_tval();
+                CmplSymbol {
+                    const_val: Some(CTimeVal::Type(typeval.clone())),
+                    typeval,
+                    var: None,
+                    lifetime: None,
+                    is_unsafe: false,
+                }
+            },
+
+            ExprEnum::TypeNullable(pointer_type) => {
+                let pointer_typeval = self.resolve_expr(pointer_type).typeval;
+                let sub_typeval = match pointer_typeval.as_enum() {
+                    TypeValEnum::Pointer(sub_typeval) => *sub_typeval.clone(),
+                    _ => {
+                        self.emit_diagnostic(pointer_type.get_span(), "InvalidNullable", format!("only pointers can be null, but got `{pointer_typeval}`").as_str());
+                        pointer_typeval
+                    },
+                };
+
+                let typeval = TypeValEnum::NullablePointer(Box::new(sub_typeval)).to_tval();
+                CmplSymbol {
+                    const_val: Some(CTimeVal::Type(typeval.clone())),
+                    typeval,
+                    var: None,
+                    lifetime: None,
+                    is_unsafe: false,
+                }
+            },
+
+            ExprEnum::TypeUnit => CmplSymbol::void(),
+
+            ExprEnum::TypeFunction(param_types, return_type) => {
+                let param_typevals = param_types.iter().map(|param_type| self.resolve_expr(param_type).typeval).collect();
+                let return_typeval = return_type.as_ref().map(|return_type| self.resolve_expr(return_type).typeval).unwrap_or_default();
+
+                let typeval = TypeValEnum::FunctionPointer(param_typevals, Box::new(return_typeval)).to_tval();
+                CmplSymbol {
+                    const_val: Some(CTimeVal::Type(typeval.clone())),
+                    typeval,
+                    var: None,
+                    lifetime: None,
+                    is_unsafe: false,
+                }
+            },
+            
+            ExprEnum::IntLit(int) => CmplSymbol {
+                const_val: Some(CTimeVal::Int(*int as i128)),
+                typeval: TypeValEnum::Int(IntKind::U64).to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::BoolLit(boolean) => CmplSymbol {
+                const_val: Some(CTimeVal::Bool(*boolean)),
+                typeval: TypeValEnum::Bool.to_tval(),
                 var: None,
                 lifetime: None,
                 is_unsafe: false,
             },
 
-            ExprEnum::TypeString => CmplSymbol {
-                const_val: Some(CTimeVal::Type(TypeValEnum::StringSlice.to_tval())),
-                typeval: TypeValEnum::StringSlice.to_tval(),
+            // null fits every nullable pointer
+            ExprEnum::NullLit => CmplSymbol {
+                const_val: Some(CTimeVal::Int(0)),
+                typeval: TypeValEnum::NullablePointer(Box::new(TypeValEnum::Unit.to_tval())).to_tval(),
                 var: None,
                 lifetime: None,
                 is_unsafe: false,
             },
 
-            ExprEnum::TypeUnit => CmplSymbol::void(),
-            
-            ExprEnum::IntLit(int) => CmplSymbol {
-                const_val: Some(CTimeVal::Int(*int as i128)),
-                typeval: TypeValEnum::UInt64.to_tval(),
+            ExprEnum::CharLit(byte) => CmplSymbol {
+                const_val: Some(CTimeVal::Int(*byte as i128)),
+                typeval: TypeValEnum::Char.to_tval(),
                 var: None,
                 lifetime: None,
                 is_unsafe: false,
             },
 
+            ExprEnum::ArrayLit(elements) => {
+                // the first element decides the element type, unless a declaration gave one
+                // (the parser never produces an empty literal, `[]` starts a slice type)
+                let element_typeval = self.resolve_expr(&elements[0]).typeval;
+
+                // the literal lives as long as the shortest lived element
+                let mut lifetime: Option<Lifetime> = None;
+                let mut is_unsafe = false;
+                for element in elements {
+                    let element_symbol = self.resolve_expr(element);
+                    is_unsafe |= element_symbol.is_unsafe;
+                    if let Some(element_lifetime) = element_symbol.lifetime
+                        && lifetime.as_ref().is_none_or(|lifetime| element_lifetime < *lifetime) {
+                        lifetime = Some(element_lifetime);
+                    }
+                }
+
+                CmplSymbol {
+                    const_val: None,
+                    typeval: TypeValEnum::Array(Box::new(element_typeval), elements.len()).to_tval(),
+                    var: None,
+                    lifetime,
+                    is_unsafe,
+                }
+            },
+
             ExprEnum::StringLit(string) => CmplSymbol {
                 const_val: Some(CTimeVal::StringSlice(self.new_static_string(string), string.len())),
                 typeval: TypeValEnum::StringSlice.to_tval(),
@@ -1135,22 +2707,102 @@ impl<'a> IRGen<'a> {
                         },
                     }
                 } else {
+                    // a nullable pointer still has a type behind it, dereferencing it is reported in `gen_expr`
+                    let typeval = match symbol.typeval.as_enum() {
+                        TypeValEnum::NullablePointer(sub_typeval) => *sub_typeval.clone(),
+                        _ => symbol.typeval.to_lessptr(),
+                    };
                     CmplSymbol {
                         const_val: None,
-                        typeval: symbol.typeval.to_lessptr(),
+                        typeval,
                         var: None,
                         lifetime: None,
                         is_unsafe: false,
                     }
                 }
             },
+
+            ExprEnum::Negate(subexpr) => {
+                let symbol = self.resolve_expr(subexpr);
+                match (symbol.typeval.as_enum(), symbol.const_val) {
+                    // a negated constant is signed, even if the literal itself was not
+                    (TypeValEnum::Int(kind), Some(CTimeVal::Int(int))) => {
+                        let kind = if kind.is_signed() { *kind } else { IntKind::I64 };
+                        CmplSymbol {
+                            const_val: Some(-int).filter(|int| kind.contains(*int)).map(CTimeVal::Int),
+                            typeval: TypeValEnum::Int(kind).to_tval(),
+                            var: None,
+                            lifetime: None,
+                            is_unsafe: false,
+                        }
+                    },
+
+                    _ => CmplSymbol {
+                        const_val: None,
+                        typeval: symbol.typeval,
+                        var: None,
+                        lifetime: None,
+                        is_unsafe: false,
+                    },
+                }
+            },
+
+            ExprEnum::BitNot(subexpr) => {
+                let symbol = self.resolve_expr(subexpr);
+                let const_val = match (symbol.typeval.as_enum(), symbol.const_val) {
+                    (TypeValEnum::Int(kind), Some(CTimeVal::Int(int))) => Some(CTimeVal::Int(kind.wrap(!int))),
+                    _ => None,
+                };
+
+                CmplSymbol {
+                    const_val,
+                    typeval: symbol.typeval,
+                    var: None,
+                    lifetime: None,
+                    is_unsafe: false,
+                }
+            },
+
+            ExprEnum::Not(subexpr) => CmplSymbol {
+                const_val: match self.resolve_expr(subexpr).const_val {
+                    Some(CTimeVal::Bool(boolean)) => Some(CTimeVal::Bool(!boolean)),
+                    _ => None,
+                },
+                typeval: TypeValEnum::Bool.to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::Cast(subexpr, type_expr) => {
+                let symbol = self.resolve_expr(subexpr);
+                let typeval = self.resolve_expr(type_expr).typeval;
+
+                // a cast pointer borrows what the original did, one made from a number is unchecked
+                let is_reinterpreted = typeval.is_borrowed() && !Self::converts_implicitly(&symbol, &typeval);
+
+                let const_val = match (typeval.as_enum(), symbol.const_val) {
+                    (TypeValEnum::Int(kind), Some(CTimeVal::Int(int))) => Some(CTimeVal::Int(kind.wrap(int))),
+                    (TypeValEnum::Int(_), Some(CTimeVal::Bool(boolean))) => Some(CTimeVal::Int(boolean as i128)),
+                    (TypeValEnum::Char, Some(CTimeVal::Int(int))) => Some(CTimeVal::Int(IntKind::U8.wrap(int))),
+                    _ => None,
+                };
+
+                CmplSymbol {
+                    const_val,
+                    lifetime: if typeval.is_borrowed() { symbol.lifetime } else { None },
+                    is_unsafe: symbol.is_unsafe || is_reinterpreted,
+                    typeval,
+                    var: None,
+                }
+            },
             
             ExprEnum::Reference(subexpr) => {
                 let symbol = self.resolve_expr(subexpr);
                 if let Some(const_val) = symbol.const_val {
                     match const_val {
                         CTimeVal::Type(..) => {
-                            self.emit_diagnostic(expr.get_loc(), "unable to grab a reference to a type (did you mean: `*u64`)");
+                            self.emit_diagnostic(expr.get_span(), "RefType", "unable to grab a reference to a type (did you mean: `*u64`)");
                             CmplSymbol {
                                 const_val: Some(CTimeVal::Type(symbol.typeval.clone().to_ptr())),
                                 typeval: symbol.typeval.to_ptr(),
@@ -1171,21 +2823,66 @@ impl<'a> IRGen<'a> {
                     }
                 } else {
                     // ref to
+                    let lifetime = if let Some(var) = symbol.var {
+                        Some(var.lifetime)
+                    } else if let ExprEnum::Index(value, _) = subexpr.as_enum() {
+                        // an element picked at runtime lives in the array, or wherever the slice points
+                        let value_symbol = self.resolve_expr(value);
+                        match value_symbol.typeval.as_enum() {
+                            TypeValEnum::Array(..) => value_symbol.var.map(|var| var.lifetime),
+                            _ => value_symbol.lifetime,
+                        }
+                    } else { None };
+
                     CmplSymbol {
                         const_val: None,
                         typeval: symbol.typeval.to_ptr(),
                         var: None,
-                        lifetime: if let Some(var) = symbol.var {
-                            Some(var.lifetime)
-                        } else { None },
+                        lifetime,
                         is_unsafe: symbol.is_unsafe,
                     }
                 }
             },
 
+            ExprEnum::Index(value, index) => {
+                let symbol = self.resolve_expr(value);
+                let element_typeval = match symbol.typeval.as_enum() {
+                    TypeValEnum::StringSlice => TypeValEnum::Char.to_tval(),
+                    TypeValEnum::Array(element_typeval, _) | TypeValEnum::Slice(element_typeval) => *element_typeval.clone(),
+                    _ => TypeValEnum::Unit.to_tval(), // reported in `gen_expr`
+                };
+
+                let const_index = match self.resolve_expr(index).const_val {
+                    Some(CTimeVal::Int(int)) => usize::try_from(int).ok(),
+                    _ => None,
+                };
+                Self::resolve_element(&symbol, &element_typeval, const_index)
+            },
+
+            ExprEnum::Slice(value, ..) => {
+                let symbol = self.resolve_expr(value);
+                let (typeval, lifetime) = match symbol.typeval.as_enum() {
+                    // points into the array, like a reference
+                    TypeValEnum::Array(element_typeval, _) => (
+                        TypeValEnum::Slice(element_typeval.clone()).to_tval(),
+                        symbol.var.as_ref().map(|var| var.lifetime.clone()),
+                    ),
+                    TypeValEnum::Slice(..) | TypeValEnum::StringSlice => (symbol.typeval.clone(), symbol.lifetime.clone()),
+                    _ => (TypeValEnum::Unit.to_tval(), None), // reported in `gen_expr`
+                };
+
+                CmplSymbol {
+                    const_val: None,
+                    typeval,
+                    var: None,
+                    lifetime,
+                    is_unsafe: symbol.is_unsafe,
+                }
+            },
+
             ExprEnum::Call(function, args) => {
                 let symbol = self.resolve_expr(function);
-                self.resolve_call(&symbol, args)
+                self.resolve_call(&symbol, args, expr.get_span())
             },
 
             ExprEnum::Variable(name) => {
@@ -1195,11 +2892,14 @@ impl<'a> IRGen<'a> {
                         const_val: var.const_val.clone(),
                         typeval: var.typeval.clone(),
                         var: Some(var.clone()),
-                        lifetime: if var.typeval.is_ptr() { Some(var.lifetime.clone()) } else { None },
+                        lifetime: if var.typeval.is_borrowed() { Some(var.lifetime.clone()) } else { None },
                         is_unsafe: var.is_unsafe,
                     }
                 } else {
-                    self.emit_diagnostic(expr.get_loc(), "⚠️ unable to resolve this variable (type is void)");
+                    // locals of enclosing functions are reported as not captured
+                    if !self.is_enclosing_local(name) {
+                        self.emit_warning(expr.get_span(), "UnresolvedVariable", "unable to resolve this variable (type is void)");
+                    }
                     CmplSymbol {
                         const_val: None,
                         typeval: TypeValEnum::Unit.to_tval(),
@@ -1212,29 +2912,81 @@ impl<'a> IRGen<'a> {
 
             ExprEnum::MemberAccess(namespace_expr, name) => {
                 let namespace_symbol = self.resolve_expr(namespace_expr);
-                if let Some(const_val) = namespace_symbol.const_val {
-                    match const_val {
-                        CTimeVal::Namespace(map) => {
-                            let var = map.get(name);
-                            if let Some(var) = var {
-                                CmplSymbol {
-                                    const_val: var.const_val.clone(),
-                                    typeval: var.typeval.clone(),
-                                    var: Some(var.clone()),
-                                    lifetime: None,
-                                    is_unsafe: false,
-                                }
-                            } else {
-                                CmplSymbol {
-                                    const_val: None,
-                                    typeval: TypeValEnum::Unit.to_tval(),
-                                    var: None,
-                                    lifetime: None,
-                                    is_unsafe: false,
-                                }
+                let var = match namespace_symbol.const_val {
+                    Some(CTimeVal::Namespace(map)) => {
+                        let var = map.get(name).cloned();
+                        if var.is_none() {
+                            self.emit_diagnostic(expr.get_span(), "MemberNotFound", format!("no member named `{name}` in this namespace").as_str());
+                        }
+                        var
+                    },
+
+                    Some(CTimeVal::StringSlice(_, len)) if name == "len" => return CmplSymbol {
+                        const_val: Some(CTimeVal::Int(len as i128)),
+                        typeval: TypeValEnum::Int(IntKind::U64).to_tval(),
+                        var: None,
+                        lifetime: None,
+                        is_unsafe: false,
+                    },
+
+                    Some(..) => {
+                        self.emit_diagnostic(expr.get_span(), "NotANamespace", format!("cannot access member `{name}`, expected a namespace").as_str());
+                        None
+                    },
+
+                    None => match namespace_symbol.typeval.as_enum() {
+                        TypeValEnum::Class(class) => {
+                            if let Some((offset, field_typeval)) = class.field(name) {
+                                return Self::resolve_field(&namespace_symbol, class, name, offset, field_typeval)
+                            } else if let Some(method) = self.resolve_method(&namespace_symbol, name) {
+                                return method
                             }
+
+                            self.emit_diagnostic(expr.get_span(), "MemberNotFound", format!("no field or method named `{name}` in class `{}`", namespace_symbol.typeval).as_str());
+                            None
+                        },
+
+                        // only methods, fields are reached through `*`
+                        TypeValEnum::Pointer(..) if Self::method_class(&namespace_symbol.typeval).is_some() => {
+                            if let Some(method) = self.resolve_method(&namespace_symbol, name) {
+                                return method
+                            }
+
+                            self.emit_diagnostic(expr.get_span(), "MemberNotFound", format!("no method named `{name}` in class `{}`", namespace_symbol.typeval.clone().to_lessptr()).as_str());
+                            None
+                        },
+
+                        TypeValEnum::Array(_, len) if name == "len" => return CmplSymbol {
+                            const_val: Some(CTimeVal::Int(*len as i128)),
+                            typeval: TypeValEnum::Int(IntKind::U64).to_tval(),
+                            var: None,
+                            lifetime: None,
+                            is_unsafe: false,
+                        },
+
+                        // read only, unlike a field
+                        TypeValEnum::StringSlice | TypeValEnum::Slice(..) if name == "len" => return CmplSymbol {
+                            const_val: None,
+                            typeval: TypeValEnum::Int(IntKind::U64).to_tval(),
+                            var: None,
+                            lifetime: None,
+                            is_unsafe: false,
                         },
-                        _ => unimplemented!(),
+
+                        _ => {
+                            self.emit_diagnostic(expr.get_span(), "NotANamespace", format!("cannot access member `{name}` on a runtime value of type `{}`", namespace_symbol.typeval).as_str());
+                            None
+                        },
+                    },
+                };
+
+                if let Some(var) = var {
+                    CmplSymbol {
+                        const_val: var.const_val.clone(),
+                        typeval: var.typeval.clone(),
+                        var: Some(var),
+                        lifetime: None,
+                        is_unsafe: false,
                     }
                 } else {
                     CmplSymbol {
@@ -1260,23 +3012,111 @@ impl<'a> IRGen<'a> {
 
             ExprEnum::Block(block, _is_unsafe_block) => self.resolve_block(block),
 
-            ExprEnum::Function(body, return_type, params) => {                
-                let symbol = if let Some(return_type) = return_type {
-                    self.resolve_expr(return_type)
-                } else {
-                    self.resolve_block(body)
+            ExprEnum::Class(fields) => self.resolve_class(fields, None),
+
+            ExprEnum::OverloadSet(candidates, meta_funcs) => self.resolve_overload_set(candidates, meta_funcs),
+
+            ExprEnum::ClassLit(class_expr, field_inits) => {
+                let class_symbol = self.resolve_expr(class_expr);
+                let typeval = match class_symbol.const_val {
+                    Some(CTimeVal::Type(typeval)) if matches!(typeval.as_enum(), TypeValEnum::Class(..)) => typeval,
+                    _ => TypeValEnum::Unit.to_tval(),
                 };
 
+                // the literal lives as long as the shortest lived field
+                let mut lifetime: Option<Lifetime> = None;
+                let mut is_unsafe = false;
+                for field_init in field_inits {
+                    if let StmtEnum::VarDecl(_, Some(init), ..) = field_init.as_enum() {
+                        let init_symbol = self.resolve_expr(init);
+                        is_unsafe |= init_symbol.is_unsafe;
+                        if let Some(init_lifetime) = init_symbol.lifetime
+                            && lifetime.as_ref().is_none_or(|lifetime| init_lifetime < *lifetime) {
+                            lifetime = Some(init_lifetime);
+                        }
+                    }
+                }
+
+                CmplSymbol {
+                    const_val: None,
+                    typeval,
+                    var: None,
+                    lifetime,
+                    is_unsafe,
+                }
+            },
+
+            // not `CmplSymbol::void()`, these are never compile time values
+            ExprEnum::While(..) | ExprEnum::Break(..) | ExprEnum::Continue => CmplSymbol {
+                const_val: None,
+                typeval: TypeValEnum::Unit.to_tval(),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            // the type comes from `break` values or arms that may use variables declared
+            // inside of them, so it is only known after generating the expression
+            ExprEnum::Loop(..) | ExprEnum::Select(..) => CmplSymbol {
+                const_val: None,
+                typeval: self.resolve_by_trial(expr),
+                var: None,
+                lifetime: None,
+                is_unsafe: false,
+            },
+
+            ExprEnum::Function(body, return_type, params, captures) => {
+                // an inferred return type is only known once the params and captures are in scope
+                let declared_symbol = return_type.as_ref().map(|return_type| self.resolve_expr(return_type));
+
                 let is_global_scope = self.has_local_scope();
                 let jump_over = self.cprog.count_ir();
                 if is_global_scope {
                     self.emit_node(IRNode::JumpFromOffset(0));
                 }
 
+                // `self` is only typed inside of a class declaration
+                let receiver_kind = params.first().and_then(Self::receiver_kind);
+                let has_receiver = receiver_kind.is_some();
+                let receiver = match (receiver_kind, self.method_classes.last()) {
+                    (Some(by_ref), Some(class_typeval)) => Some((by_ref, class_typeval.clone())),
+                    (Some(_), None) => {
+                        self.emit_diagnostic(params[0].get_span(), "InvalidReceiver", "`self` is only allowed in the methods of a class");
+                        None
+                    },
+                    _ => None,
+                };
+
+                if let (true, Some(capture)) = (has_receiver, captures.first()) {
+                    self.emit_diagnostic(&capture.span, "InvalidCapture", "methods cannot capture variables, only their receiver");
+                }
+
+                // captures are found before the locals of the enclosing function go out of sight
+                let mut capture_vars = Vec::new();
+                let mut lifetime: Option<Lifetime> = None;
+                let mut is_unsafe = false;
+                for capture in captures.iter().filter(|_| !has_receiver) {
+                    let Some(var) = self.lookup_capture(capture) else {
+                        continue
+                    };
+
+                    let borrowed = if capture.by_ref { Some(&var.lifetime) } else { var.typeval.is_borrowed().then_some(&var.lifetime) };
+                    if let Some(borrowed) = borrowed
+                        && lifetime.as_ref().is_none_or(|lifetime| borrowed < lifetime) {
+                        lifetime = Some(borrowed.clone());
+                    }
+                    is_unsafe |= var.is_unsafe;
+                    capture_vars.push((capture.by_ref, var));
+                }
+
+                let outer_stack_sz = std::mem::take(&mut self.stack_sz);
+                let outer_scopes = std::mem::take(&mut self.scopes);
+                self.enclosing_scopes.push(outer_scopes);
+
                 self.open_scope();
 
                 let mut param_types = Vec::new();
-                for param in params {
+                for param in params.iter().skip(has_receiver as usize) {
                     match param.as_enum() {
                         StmtEnum::ConstDecl(name, init, type_expr, _) => {
                             let typeval = if let Some(type_expr) = type_expr {
@@ -1288,12 +3128,13 @@ impl<'a> IRGen<'a> {
                             };
 
                             param_types.push(typeval.clone());
-                            self.stack_sz += typeval.size_of();
+                            self.stack_sz += typeval.stack_size_of();
                             let param_var = Variable {
                                 name: name.clone(),
                                 typeval,
                                 global_pos: None,
                                 stack_loc: Some(self.stack_sz),
+                                indirect: None,
                                 const_val: None,
                                 external: None,
                                 is_alias: false,
@@ -1307,16 +3148,152 @@ impl<'a> IRGen<'a> {
                     }
                 }
 
+                // by-copy captures are plain locals, by-reference ones are reached through their pointer
+                let mut capture_types = Vec::new();
+                let mut pointers_size = 0;
+                for (by_ref, var) in &capture_vars {
+                    let typeval = if *by_ref { var.typeval.clone().to_ptr() } else { var.typeval.clone() };
+                    self.stack_sz += typeval.stack_size_of();
+                    let capture_var = if *by_ref {
+                        pointers_size += SIZE_64;
+                        Variable {
+                            name: var.name.clone(),
+                            typeval: var.typeval.clone(),
+                            global_pos: None,
+                            stack_loc: None,
+                            indirect: Some((self.stack_sz, 0)),
+                            const_val: None,
+                            external: None,
+                            is_alias: false,
+                            lifetime: self.make_lifetime(-1), // outlives every call
+                            is_unsafe: var.is_unsafe,
+                        }
+                    } else {
+                        Variable {
+                            name: var.name.clone(),
+                            typeval: typeval.clone(),
+                            global_pos: None,
+                            stack_loc: Some(self.stack_sz),
+                            indirect: None,
+                            const_val: None,
+                            external: None,
+                            is_alias: false,
+                            lifetime: self.make_lifetime(1),
+                            is_unsafe: var.is_unsafe,
+                        }
+                    };
+
+                    capture_types.push(typeval);
+                    self.add_var(capture_var);
+                }
+
+                // the receiver is passed by its address like a capture, `&self` is reached through it
+                let mut receiver_loc = 0;
+                if let Some((by_ref, class_typeval)) = &receiver {
+                    self.stack_sz += SIZE_64;
+                    pointers_size += SIZE_64;
+                    receiver_loc = self.stack_sz;
+
+                    if *by_ref {
+                        let receiver_var = Variable {
+                            name: "self".to_string(),
+                            typeval: class_typeval.clone(),
+                            global_pos: None,
+                            stack_loc: None,
+                            indirect: Some((receiver_loc, 0)),
+                            const_val: None,
+                            external: None,
+                            is_alias: false,
+                            lifetime: self.make_lifetime(-1), // outlives every call
+                            is_unsafe: false,
+                        };
+                        self.add_var(receiver_var);
+                    }
+                }
+
+                let is_closure = !has_receiver && !captures.is_empty();
+                if is_closure || has_receiver {
+                    // the called address stays under the return address
+                    self.stack_sz += ADDRESS_SIZE;
+                    pointers_size += ADDRESS_SIZE;
+                }
+
                 // return address
                 self.stack_sz += SIZE_64;
 
+                // `break` and `continue` cannot reach loops outside of the function
+                let outer_loops = std::mem::take(&mut self.loops);
+
                 let address = self.cprog.count_ir();
-                self.gen_block(body, false, expr.get_loc());
-                
-                let params_size = self.close_scope_noclean();
+
+                // `self` is copied out of the receiver into a scope of its own, above the return address
+                let receiver_copy_size = match &receiver {
+                    Some((false, class_typeval)) => {
+                        self.open_scope();
+                        self.emit_node(IRNode::StackReadPush64(self.stack_sz - receiver_loc));
+                        self.stack_sz += SIZE_64;
+                        self.gen_load(class_typeval.stack_size_of());
+
+                        let receiver_var = Variable {
+                            name: "self".to_string(),
+                            typeval: class_typeval.clone(),
+                            global_pos: None,
+                            stack_loc: Some(self.stack_sz),
+                            indirect: None,
+                            const_val: None,
+                            external: None,
+                            is_alias: false,
+                            lifetime: self.lifetime_here(),
+                            is_unsafe: false,
+                        };
+                        self.add_var(receiver_var);
+                        Some(class_typeval.stack_size_of())
+                    },
+                    _ => None,
+                };
+
+                let symbol = match declared_symbol {
+                    Some(symbol) => symbol,
+                    None => self.resolve_block(body),
+                };
+
+                self.gen_block(body, false, expr.get_span());
+
+                // the value of the body is returned as the declared type
+                if return_type.is_some()
+                    && let Some(return_expr) = &body.return_expr {
+                    let return_symbol = self.resolve_expr(return_expr);
+                    if !Self::converts_implicitly(&return_symbol, &symbol.typeval) {
+                        if Self::byte_kind(&return_symbol.typeval).is_some() && Self::byte_kind(&symbol.typeval).is_some() {
+                            self.check_int_conversion(&return_symbol, &symbol.typeval, return_expr.get_span());
+                        } else {
+                            self.emit_diagnostic(return_expr.get_span(), "ReturnTypeMismatch", format!("expected `{}`, but got `{}`", symbol.typeval, return_symbol.typeval).as_str());
+                        }
+                    }
+                }
+
+                if let Some(copy_size) = receiver_copy_size {
+                    self.gen_squash(0, symbol.typeval.stack_size_of(), copy_size);
+                    self.close_scope_noclean();
+                }
+
+                // everything local to the function is at least one scope deep
+                if let Some(return_expr) = &body.return_expr
+                    && let Some(return_symbol) = self.symbol_cache.get(&(&**return_expr as *const Expr))
+                    && return_symbol.lifetime.as_ref().is_some_and(|lifetime| lifetime.scope_depth > 0) {
+                    match return_symbol.typeval.as_enum() {
+                        TypeValEnum::Closure(..) => self.emit_diagnostic(return_expr.get_span(), "LifetimeTooShort", "closure may not outlive the variables it borrows"),
+                        TypeValEnum::MethodPointer(..) => self.emit_diagnostic(return_expr.get_span(), "LifetimeTooShort", "method may not outlive the value it is bound to"),
+                        _ => {},
+                    }
+                }
+
+                self.loops = outer_loops;
+
+                let params_size = self.close_scope_noclean() + pointers_size;
                 self.stack_sz -= params_size + (SIZE_64 /* return address */);
 
-                self.pop_to_stack(&symbol.typeval, symbol.typeval.size_of() + (params_size + SIZE_64 /* return address */));
+                self.pop_to_stack(&symbol.typeval, symbol.typeval.stack_size_of() + (params_size + SIZE_64 /* return address */));
                 self.emit_node(IRNode::Return { params_size });
 
                 if is_global_scope {
@@ -1331,6 +3308,33 @@ impl<'a> IRGen<'a> {
                     }
                 }
 
+                self.scopes = self.enclosing_scopes.pop().unwrap();
+                self.stack_sz = outer_stack_sz;
+
+                if has_receiver {
+                    return CmplSymbol {
+                        const_val: Some(CTimeVal::Function {
+                            address,
+                            return_typeval: symbol.typeval.clone(),
+                        }),
+                        typeval: TypeValEnum::MethodPointer(param_types, Box::new(symbol.typeval)).to_tval(),
+                        var: None,
+                        lifetime: None,
+                        is_unsafe: false,
+                    }
+                }
+
+                if is_closure {
+                    self.closure_addresses.insert(expr as *const Expr, address);
+                    return CmplSymbol {
+                        const_val: None,
+                        typeval: TypeValEnum::Closure(param_types, Box::new(symbol.typeval), capture_types).to_tval(),
+                        var: None,
+                        lifetime,
+                        is_unsafe,
+                    }
+                }
+
                 let function_symbol = CmplSymbol {
                     const_val: Some(CTimeVal::Function {
                         address,
@@ -1342,32 +3346,96 @@ impl<'a> IRGen<'a> {
                     is_unsafe: false,
                 };
 
-                function_symbol
-            },
+                function_symbol
+            },
+
+            ExprEnum::CompoundAssign { .. } => CmplSymbol::void(),
+
+            ExprEnum::BinaryOp { operands, op } => {
+                let operands = &(**operands);
+                match op {
+                    Operator::Assign => {
+                        CmplSymbol {
+                            const_val: None,
+                            typeval: TypeValEnum::Unit.to_tval(),
+                            var: None,
+                            lifetime: None,
+                            is_unsafe: false,
+                        }
+                    },
+
+                    Operator::Add | Operator::Sub if self.resolve_expr(&operands.0).typeval.is_ptr() => {
+                        let lhs_symbol = self.resolve_expr(&operands.0);
+                        let rhs_symbol = self.resolve_expr(&operands.1);
+                        match rhs_symbol.typeval.as_enum() {
+                            // the distance between two pointers is just a number
+                            TypeValEnum::Pointer(..) if matches!(op, Operator::Sub) => CmplSymbol {
+                                const_val: None,
+                                typeval: TypeValEnum::Int(IntKind::I64).to_tval(),
+                                var: None,
+                                lifetime: None,
+                                is_unsafe: false,
+                            },
+
+                            // a moved pointer still borrows the same storage, but nothing checks that it stays inside it
+                            _ => CmplSymbol {
+                                const_val: None,
+                                typeval: lhs_symbol.typeval,
+                                var: None,
+                                lifetime: lhs_symbol.lifetime,
+                                is_unsafe: true,
+                            },
+                        }
+                    },
+
+                    Operator::And | Operator::Or => {
+                        let lhs_symbol = self.resolve_expr(&operands.0);
+                        let rhs_symbol = self.resolve_expr(&operands.1);
+
+                        let const_val = match (lhs_symbol.const_val, rhs_symbol.const_val) {
+                            (Some(CTimeVal::Bool(x)), Some(CTimeVal::Bool(y))) => Some(CTimeVal::Bool(match op {
+                                Operator::And => x && y,
+                                _ => x || y,
+                            })),
+                            _ => None,
+                        };
+
+                        CmplSymbol {
+                            const_val,
+                            typeval: TypeValEnum::Bool.to_tval(),
+                            var: None,
+                            lifetime: None,
+                            is_unsafe: false,
+                        }
+                    },
 
-            ExprEnum::BinaryOp { operands, op } => {
-                let operands = &(**operands);
-                match op {
-                    Operator::Assign => {
+                    // `|` between types makes a tagged union
+                    Operator::BitOr if !Self::is_int_value(&self.resolve_expr(&operands.0)) || !Self::is_int_value(&self.resolve_expr(&operands.1)) => {
+                        let lhs_symbol = self.resolve_expr(&operands.0);
+                        let rhs_symbol = self.resolve_expr(&operands.1);
+
+                        let new_typeval = Self::mix_tagged_unions(&lhs_symbol.typeval, &rhs_symbol.typeval);
                         CmplSymbol {
-                            const_val: None,
-                            typeval: TypeValEnum::Unit.to_tval(),
+                            const_val: Some(CTimeVal::Type(new_typeval.clone())),
+                            typeval: new_typeval,
                             var: None,
                             lifetime: None,
                             is_unsafe: false,
                         }
                     },
 
-                    Operator::Add | Operator::Sub => {
+                    Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Mod
+                    | Operator::BitOr | Operator::BitAnd | Operator::BitXor | Operator::Shl | Operator::Shr => {
                         let lhs_symbol = self.resolve_expr(&operands.0);
                         let rhs_symbol = self.resolve_expr(&operands.1);
+                        let kind = match op {
+                            Operator::Shl | Operator::Shr => Self::shifted_kind(&lhs_symbol),
+                            _ => Self::operand_kind(&lhs_symbol, &rhs_symbol),
+                        };
 
                         // calculate at ctime if both operands are ctimevals
-                        let calculate = |x: i128, y: i128| match op {
-                            Operator::Add => x + y,
-                            Operator::Sub => x - y,
-                            _ => unreachable!(),
-                        };
+                        // (dividing by zero, shifting too far and results outside of the kind are not folded, they are reported in `gen_expr`)
+                        let calculate = |x: i128, y: i128| Self::fold_int(*op, kind, x, y).filter(|int| kind.contains(*int));
 
                         let const_val = unsafe {
                             if lhs_symbol.const_val.is_some() && rhs_symbol.const_val.is_some() {
@@ -1378,8 +3446,7 @@ impl<'a> IRGen<'a> {
 
                                 match const_operands {
                                     (CTimeVal::Int(x), CTimeVal::Int(y)) => {
-                                        let result = calculate(x, y);
-                                        Some(CTimeVal::Int(result))
+                                        calculate(x, y).map(CTimeVal::Int)
                                     },
                                     _ => None,
                                 }
@@ -1390,38 +3457,657 @@ impl<'a> IRGen<'a> {
 
                         CmplSymbol {
                             const_val,
-                            typeval: TypeValEnum::UInt64.to_tval(),
+                            typeval: TypeValEnum::Int(kind).to_tval(),
                             var: None,
                             lifetime: None,
                             is_unsafe: false,
                         }
                     },
 
-                    Operator::BitOr => {
+                    Operator::Eq | Operator::NotEq
+                    | Operator::Less | Operator::LessEq
+                    | Operator::Greater | Operator::GreaterEq => {
                         let lhs_symbol = self.resolve_expr(&operands.0);
                         let rhs_symbol = self.resolve_expr(&operands.1);
 
-                        let new_typeval = Self::mix_tagged_unions(&lhs_symbol.typeval, &rhs_symbol.typeval);
+                        // compare at ctime if both operands are ctimevals
+                        let is_comparable = Self::unify_ints(&lhs_symbol, &rhs_symbol).is_some()
+                            || Self::check_comparison(*op, &lhs_symbol.typeval, &rhs_symbol.typeval).is_ok();
+                        let const_val = match (lhs_symbol.const_val, rhs_symbol.const_val) {
+                            _ if !is_comparable => None, // reported in `gen_expr`
+                            (Some(CTimeVal::Int(x)), Some(CTimeVal::Int(y))) => Some(CTimeVal::Bool(match op {
+                                Operator::Eq => x == y,
+                                Operator::NotEq => x != y,
+                                Operator::Less => x < y,
+                                Operator::LessEq => x <= y,
+                                Operator::Greater => x > y,
+                                Operator::GreaterEq => x >= y,
+                                _ => unreachable!(),
+                            })),
+
+                            (Some(CTimeVal::Bool(x)), Some(CTimeVal::Bool(y))) => match op {
+                                Operator::Eq => Some(CTimeVal::Bool(x == y)),
+                                Operator::NotEq => Some(CTimeVal::Bool(x != y)),
+                                _ => None, // reported in `gen_expr`
+                            },
+
+                            _ => None,
+                        };
+
                         CmplSymbol {
-                            const_val: Some(CTimeVal::Type(new_typeval.clone())),
-                            typeval: new_typeval,
+                            const_val,
+                            typeval: TypeValEnum::Bool.to_tval(),
                             var: None,
                             lifetime: None,
                             is_unsafe: false,
                         }
+                    },
+                }
+            },
+        }
+    }
+
+    /// Generates the expression once without a known type and rolls everything back
+    fn resolve_by_trial(&mut self, expr: &'a Expr) -> TypeVal {
+        if let Some(typeval) = self.trial_types.get(&(expr as *const Expr)) {
+            return typeval.clone()
+        }
+
+        let prev_ir_count = self.cprog.count_ir();
+        let prev_stack_sz = self.stack_sz;
+        let prev_symbol_cache = self.symbol_cache.clone();
+
+        self.diagnostics_lock += 1;
+        let typeval = match expr.as_enum() {
+            ExprEnum::Loop(body) => self.gen_loop(None, body, None, expr.get_span()),
+            ExprEnum::Select(value, arms) => self.gen_select(value, arms, None, expr.get_span()),
+            _ => unreachable!(),
+        };
+        self.diagnostics_lock -= 1;
+
+        self.symbol_cache = prev_symbol_cache;
+        self.stack_sz = prev_stack_sz;
+        self.cprog.shift_nodes(prev_ir_count..=(self.cprog.ir_pos()));
+
+        self.trial_types.insert(expr as *const Expr, typeval.clone());
+        typeval
+    }
+
+    fn resolve_class(&mut self, fields: &'a Vec<Stmt>, name: Option<&str>) -> CmplSymbol {
+        let mut class_fields: Vec<(String, TypeVal)> = Vec::new();
+        for field in fields {
+            match field.as_enum() {
+                StmtEnum::VarDecl(field_name, _, Some(type_expr), _) => {
+                    let typeval = self.resolve_expr(type_expr).typeval;
+                    if class_fields.iter().any(|(other_name, _)| other_name == field_name) {
+                        self.emit_diagnostic(field.get_span(), "DuplicateField", format!("field `{field_name}` is declared more than once").as_str());
+                    } else {
+                        class_fields.push((field_name.clone(), typeval));
                     }
+                },
+                StmtEnum::ConstDecl(..) => {}, // methods need the finished class
+                _ => unreachable!(),
+            }
+        }
+
+        let class = Rc::new(ClassInfo::new(self.class_id_counter, name.map(str::to_string), class_fields));
+        self.class_id_counter += 1;
+
+        let typeval = TypeValEnum::Class(class.clone()).to_tval();
+
+        // methods may take and return their own class, the declaration only adds its name once it is done
+        if let Some(name) = name {
+            let class_var = Variable {
+                name: name.to_string(),
+                typeval: typeval.clone(),
+                global_pos: None,
+                stack_loc: None,
+                indirect: None,
+                const_val: Some(CTimeVal::Type(typeval.clone())),
+                external: None,
+                is_alias: false,
+                lifetime: self.lifetime_here(),
+                is_unsafe: false,
+            };
+
+            if self.has_local_scope() {
+                self.add_var(class_var);
+            } else {
+                self.add_global(class_var);
+            }
+        }
+
+        self.method_classes.push(typeval.clone());
+        for field in fields {
+            let StmtEnum::ConstDecl(method_name, Some(init), ..) = field.as_enum() else {
+                continue
+            };
+
+            let key = (class.id, method_name.clone());
+            if class.field(method_name).is_some() || self.methods.contains_key(&key) {
+                self.emit_diagnostic(field.get_span(), "DuplicateField", format!("field `{method_name}` is declared more than once").as_str());
+                continue
+            }
+
+            let method = self.resolve_expr(init);
+            if let TypeValEnum::MethodPointer(..) = method.typeval.as_enum() {
+                self.methods.insert(key, method);
+            } else {
+                self.emit_diagnostic(init.get_span(), "InvalidMethod", format!("method `{method_name}` must take `self` or `&self` as its first parameter").as_str());
+            }
+        }
+        self.method_classes.pop();
+
+        CmplSymbol {
+            const_val: Some(CTimeVal::Type(typeval.clone())),
+            typeval,
+            var: None,
+            lifetime: None,
+            is_unsafe: false,
+        }
+    }
+
+    /// `Some(true)` for `&self`, `Some(false)` for `self`
+    fn receiver_kind(param: &Stmt) -> Option<bool> {
+        let StmtEnum::ConstDecl(_, None, Some(type_expr), _) = param.as_enum() else {
+            return None
+        };
+
+        match type_expr.as_enum() {
+            ExprEnum::TypeSelf => Some(false),
+            ExprEnum::Dereference(sub_expr) if matches!(sub_expr.as_enum(), ExprEnum::TypeSelf) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Classes have methods, pointers to them lend theirs
+    fn method_class(typeval: &TypeVal) -> Option<&Rc<ClassInfo>> {
+        match typeval.as_enum() {
+            TypeValEnum::Class(class) => Some(class),
+            TypeValEnum::Pointer(sub_typeval) => match sub_typeval.as_enum() {
+                TypeValEnum::Class(class) => Some(class),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// `object.name` bound to its object, so it cannot outlive it
+    fn resolve_method(&self, object_symbol: &CmplSymbol, name: &str) -> Option<CmplSymbol> {
+        let class = Self::method_class(&object_symbol.typeval)?;
+        let method = self.methods.get(&(class.id, name.to_string()))?;
+
+        let lifetime = match (&object_symbol.var, object_symbol.typeval.as_enum()) {
+            (Some(var), TypeValEnum::Class(..)) => Some(var.lifetime.clone()),
+            _ => object_symbol.lifetime.clone(),
+        };
+
+        Some(CmplSymbol {
+            const_val: None,
+            typeval: method.typeval.clone(),
+            var: None,
+            lifetime,
+            is_unsafe: object_symbol.is_unsafe,
+        })
+    }
+
+    fn is_enclosing_local(&self, name: &str) -> bool {
+        self.enclosing_scopes.iter().flatten().any(|scope| scope.lookup(name).is_some())
+    }
+
+    /// Finds a local of the enclosing function for a closure to take
+    fn lookup_capture(&mut self, capture: &Capture) -> Option<Variable> {
+        let Some(var) = self.lookup_var(&capture.name).cloned() else {
+            self.emit_diagnostic(&capture.span, "InvalidCapture", format!("no variable named `{}` to capture", capture.name).as_str());
+            return None
+        };
+
+        if var.stack_loc.is_none() && var.indirect.is_none() {
+            self.emit_diagnostic(&capture.span, "InvalidCapture", format!("only local variables can be captured, `{}` can be used without capturing it", capture.name).as_str());
+            return None
+        }
+
+        Some(var)
+    }
+
+    /// A field of a variable is a variable too, so it can be assigned to and referenced
+    fn resolve_field(object_symbol: &CmplSymbol, class: &ClassInfo, name: &str, offset: usize, field_typeval: &TypeVal) -> CmplSymbol {
+        let field_var = object_symbol.var.as_ref().map(|var| Variable {
+            name: format!("{}.{name}", var.name),
+            typeval: field_typeval.clone(),
+            global_pos: var.global_pos.map(|pos| pos + offset),
+            // `stack_loc` points right above the last pushed word
+            stack_loc: var.stack_loc.map(|loc| loc - (class.stack_size_of() - offset - field_typeval.stack_size_of())),
+            indirect: var.indirect.map(|(loc, delta)| (loc, delta + class.stack_size_of() - offset - field_typeval.stack_size_of())),
+            const_val: None,
+            external: None,
+            is_alias: false,
+            lifetime: var.lifetime.clone(),
+            is_unsafe: var.is_unsafe,
+        });
+
+        let lifetime = match &field_var {
+            Some(var) if field_typeval.is_borrowed() => Some(var.lifetime.clone()),
+            Some(..) => None,
+            None => object_symbol.lifetime.clone(),
+        };
+
+        CmplSymbol {
+            const_val: None,
+            typeval: field_typeval.clone(),
+            var: field_var,
+            lifetime,
+            is_unsafe: object_symbol.is_unsafe,
+        }
+    }
+
+    /// A constant index into an array variable is a variable of its own, like a field
+    fn resolve_element(object_symbol: &CmplSymbol, element_typeval: &TypeVal, const_index: Option<usize>) -> CmplSymbol {
+        let element_size = element_typeval.stack_size_of();
+        let element_var = match (&object_symbol.var, object_symbol.typeval.as_enum(), const_index) {
+            (Some(var), TypeValEnum::Array(_, len), Some(index)) if index < *len => Some(Variable {
+                name: format!("{}[{index}]", var.name),
+                typeval: element_typeval.clone(),
+                // globals are stored in push order, the last element first
+                global_pos: var.global_pos.map(|pos| pos + (len - 1 - index) * element_size),
+                // the first element is on top of the array
+                stack_loc: var.stack_loc.map(|loc| loc - index * element_size),
+                indirect: var.indirect.map(|(loc, delta)| (loc, delta + index * element_size)),
+                const_val: None,
+                external: None,
+                is_alias: false,
+                lifetime: var.lifetime.clone(),
+                is_unsafe: var.is_unsafe,
+            }),
+            _ => None,
+        };
+
+        let lifetime = match &element_var {
+            Some(var) if element_typeval.is_borrowed() => Some(var.lifetime.clone()),
+            Some(..) => None,
+            None => object_symbol.lifetime.clone(),
+        };
+
+        CmplSymbol {
+            const_val: None,
+            typeval: element_typeval.clone(),
+            var: element_var,
+            lifetime,
+            is_unsafe: object_symbol.is_unsafe,
+        }
+    }
+
+    fn resolve_overload_set(&mut self, candidates: &'a Vec<Expr>, meta_funcs: &'a (Option<Expr>, Option<Expr>, Option<Expr>)) -> CmplSymbol {
+        let mut candidate_symbols = Vec::new();
+        for candidate in candidates {
+            // `function(type, ...)` spells out the signature, a plain function uses its own
+            let (func, declared_params) = match candidate.as_enum() {
+                ExprEnum::Call(func, declared_params) => (&**func, Some(declared_params)),
+                _ => (candidate, None),
+            };
+
+            let symbol = self.resolve_expr(func);
+            let TypeValEnum::FunctionPointer(params, _) = symbol.typeval.as_enum() else {
+                self.emit_diagnostic(func.get_span(), "InvalidOverload", format!("overload candidates must be functions, but got `{}`", symbol.typeval).as_str());
+                continue
+            };
+
+            if let Some(declared_params) = declared_params {
+                let declared: Vec<TypeVal> = declared_params.iter().map(|param| self.resolve_expr(param).typeval).collect();
+                if declared != *params {
+                    self.emit_diagnostic(candidate.get_span(), "OverloadSignatureMismatch", format!("{} is declared as taking `({})`", Self::describe_overload(&symbol), Self::join_typevals(&declared)).as_str());
                 }
+            }
+
+            candidate_symbols.push(symbol);
+        }
+
+        let meta_funcs = (
+            self.resolve_meta_func(&meta_funcs.0, "pre"),
+            self.resolve_meta_func(&meta_funcs.1, "mid"),
+            self.resolve_meta_func(&meta_funcs.2, "post"),
+        );
+
+        CmplSymbol {
+            const_val: Some(CTimeVal::DynamicFnDispatcher { candidates: candidate_symbols, meta_funcs: Box::new(meta_funcs) }),
+            typeval: TypeValEnum::Unit.to_tval(),
+            var: None,
+            lifetime: None,
+            is_unsafe: false,
+        }
+    }
+
+    fn resolve_meta_func(&mut self, func: &'a Option<Expr>, name: &str) -> CmplSymbol {
+        let Some(func) = func else {
+            return CmplSymbol::void()
+        };
+
+        let symbol = self.resolve_expr(func);
+        match symbol.typeval.as_enum() {
+            TypeValEnum::FunctionPointer(params, _) if params.is_empty() => symbol,
+            _ => {
+                self.emit_diagnostic(func.get_span(), "InvalidMetaFunction", format!("meta function `{name}` must be a function without parameters").as_str());
+                CmplSymbol::void()
             },
         }
     }
 
-    fn resolve_call(&mut self, symbol: &CmplSymbol, args: &'a Vec<Expr>) -> CmplSymbol {
+    /// Every candidate that takes exactly the types of the args
+    fn match_overloads(&mut self, candidates: &[CmplSymbol], args: &'a [Expr]) -> Vec<CmplSymbol> {
+        let arg_symbols: Vec<CmplSymbol> = args.iter().map(|arg| self.resolve_expr(arg)).collect();
+
+        // exact types win over widened integers, which win over constants that merely fit
+        let tiers: [fn(&CmplSymbol, &TypeVal) -> bool; 3] = [
+            |arg, param| arg.typeval == *param,
+            |arg, param| Self::converts_implicitly(&CmplSymbol { const_val: None, ..arg.clone() }, param),
+            Self::converts_implicitly,
+        ];
+
+        for accepts in tiers {
+            let matches: Vec<CmplSymbol> = candidates.iter()
+                .filter(|candidate| matches!(candidate.typeval.as_enum(), TypeValEnum::FunctionPointer(params, _)
+                    if params.len() == arg_symbols.len() && params.iter().zip(&arg_symbols).all(|(param, arg)| accepts(arg, param))))
+                .cloned()
+                .collect();
+
+            if !matches.is_empty() {
+                return matches
+            }
+        }
+
+        Vec::new()
+    }
+
+    fn report_overload_mismatch(&mut self, candidates: &[CmplSymbol], matches: &[CmplSymbol], args: &'a [Expr], span: &Span) {
+        let arg_typevals: Vec<TypeVal> = args.iter().map(|arg| self.resolve_expr(arg).typeval).collect();
+        let arg_typevals = Self::join_typevals(&arg_typevals);
+
+        let diagnostic = if matches.is_empty() {
+            let candidates: Vec<String> = candidates.iter().map(Self::describe_overload).collect();
+            Diagnostic::error(*span, "NoOverload", format!("no function overload for arguments `({arg_typevals})`").as_str())
+                .with_note(format!("candidates are {}", candidates.join(", ")).as_str())
+        } else {
+            let matches: Vec<String> = matches.iter().map(Self::describe_overload).collect();
+            Diagnostic::error(*span, "AmbiguousOverload", format!("call with arguments `({arg_typevals})` is ambiguous").as_str())
+                .with_note(format!("it matches {}", matches.join(", ")).as_str())
+        };
+        self.emit(diagnostic);
+    }
+
+    /// Every argument has to convert to its parameter like an initializer would
+    fn check_args(&mut self, param_typevals: &[TypeVal], args: &'a [Expr], span: &Span) {
+        if param_typevals.len() != args.len() {
+            let plural = if param_typevals.len() == 1 { "" } else { "s" };
+            self.emit_diagnostic(span, "ArgumentCount", format!("expected {} argument{plural}, but got {}", param_typevals.len(), args.len()).as_str());
+            return
+        }
+
+        for (param_typeval, arg) in param_typevals.iter().zip(args) {
+            let arg_symbol = self.resolve_expr(arg);
+            if !Self::converts_implicitly(&arg_symbol, param_typeval) {
+                self.emit_diagnostic(arg.get_span(), "ArgumentTypeMismatch", format!("expected `{param_typeval}`, but got `{}`", arg_symbol.typeval).as_str());
+            }
+        }
+    }
+
+    fn describe_overload(symbol: &CmplSymbol) -> String {
+        let name = symbol.var.as_ref().map_or("(function)", |var| var.name.as_str());
+        match symbol.typeval.as_enum() {
+            TypeValEnum::FunctionPointer(params, _) => format!("`{name}({})`", Self::join_typevals(params)),
+            _ => format!("`{name}`"),
+        }
+    }
+
+    fn join_typevals(typevals: &[TypeVal]) -> String {
+        typevals.iter().map(TypeVal::to_string).collect::<Vec<String>>().join(", ")
+    }
+
+    fn fold_int(op: Operator, kind: IntKind, x: i128, y: i128) -> Option<i128> {
+        match op {
+            Operator::Add => x.checked_add(y),
+            Operator::Sub => x.checked_sub(y),
+            Operator::Mul => x.checked_mul(y),
+            Operator::Div => x.checked_div(y),
+            Operator::Mod => x.checked_rem(y),
+            Operator::BitOr => Some(x | y),
+            Operator::BitAnd => Some(x & y),
+            Operator::BitXor => Some(x ^ y),
+            // bits shifted past the kind are an overflow, like a multiplication would be
+            Operator::Shl => Self::shift_range(kind).contains(&y).then(|| x.checked_mul(1 << y)).flatten(),
+            Operator::Shr => Self::shift_range(kind).contains(&y).then_some(x >> y),
+            _ => unreachable!("`{op:?}` is not arithmetic"),
+        }
+    }
+
+    /// Reports an operation on constants that was not folded because its result leaves the range of its kind,
+    /// only looks at `expr` itself
+    fn check_const_overflow(&mut self, expr: &'a Expr) -> bool {
+        let symbol = self.resolve_expr(expr);
+        let (TypeValEnum::Int(kind), None) = (symbol.typeval.as_enum(), &symbol.const_val) else {
+            return false
+        };
+
+        let int = match expr.as_enum() {
+            ExprEnum::BinaryOp { operands, op } => match (self.resolve_expr(&operands.0).const_val, self.resolve_expr(&operands.1).const_val) {
+                (Some(CTimeVal::Int(_)), Some(CTimeVal::Int(0))) if matches!(op, Operator::Div | Operator::Mod) => return false,
+                (Some(CTimeVal::Int(_)), Some(CTimeVal::Int(y)))
+                    if matches!(op, Operator::Shl | Operator::Shr) && !Self::shift_range(*kind).contains(&y) => return false,
+                (Some(CTimeVal::Int(x)), Some(CTimeVal::Int(y))) => Self::fold_int(*op, *kind, x, y),
+                _ => return false,
+            },
+
+            ExprEnum::Negate(subexpr) => match self.resolve_expr(subexpr).const_val {
+                Some(CTimeVal::Int(x)) => Some(-x),
+                _ => return false,
+            },
+
+            _ => return false,
+        };
+
+        let message = match int {
+            Some(int) if int < kind.min() => format!("constant expression underflows `{kind}`, it evaluates to `{int}`"),
+            Some(int) => format!("constant expression overflows `{kind}`, it evaluates to `{int}`"),
+            None => format!("constant expression overflows `{kind}`"),
+        };
+        self.emit_diagnostic(expr.get_span(), "ConstantOverflow", message.as_str());
+        true
+    }
+
+    /// Like `check_const_overflow`, but also looks through the operands, for initializers that are never generated
+    fn check_const_overflow_deep(&mut self, expr: &'a Expr) -> bool {
+        match expr.as_enum() {
+            ExprEnum::BinaryOp { operands, .. } => {
+                self.check_const_overflow_deep(&operands.0)
+                    || self.check_const_overflow_deep(&operands.1)
+                    || self.check_const_overflow(expr)
+            },
+
+            ExprEnum::Negate(subexpr) => self.check_const_overflow_deep(subexpr) || self.check_const_overflow(expr),
+            ExprEnum::BitNot(subexpr) | ExprEnum::Cast(subexpr, _) => self.check_const_overflow_deep(subexpr),
+            _ => false,
+        }
+    }
+
+    /// Integers convert without `as` if no value can change: widening within the same signedness,
+    /// or a constant that fits
+    fn converts_implicitly(symbol: &CmplSymbol, typeval: &TypeVal) -> bool {
+        if symbol.typeval == *typeval {
+            return true
+        }
+
+        match (symbol.typeval.as_enum(), typeval.as_enum(), &symbol.const_val) {
+            (TypeValEnum::Int(_), TypeValEnum::Int(kind), Some(CTimeVal::Int(int))) => kind.contains(*int),
+            (TypeValEnum::Int(from_kind), TypeValEnum::Int(kind), _) => from_kind.widens_to(*kind),
+            (TypeValEnum::Pointer(from_typeval), TypeValEnum::NullablePointer(sub_typeval), _) => from_typeval == sub_typeval,
+            (TypeValEnum::NullablePointer(..), TypeValEnum::NullablePointer(..), Some(CTimeVal::Int(0))) => true, // `null`
+            _ => false,
+        }
+    }
+
+    /// The kind two integer operands meet at, `None` if either side would need an `as`,
+    /// a constant takes the kind of the other side on either side of the operator
+    fn unify_ints(lhs: &CmplSymbol, rhs: &CmplSymbol) -> Option<IntKind> {
+        match (lhs.typeval.as_enum(), rhs.typeval.as_enum()) {
+            (TypeValEnum::Int(lhs_kind), TypeValEnum::Int(_))
+                if matches!(rhs.const_val, Some(CTimeVal::Int(_))) && Self::converts_implicitly(rhs, &lhs.typeval) => Some(*lhs_kind),
+            (TypeValEnum::Int(_), TypeValEnum::Int(rhs_kind)) if Self::converts_implicitly(lhs, &rhs.typeval) => Some(*rhs_kind),
+            (TypeValEnum::Int(lhs_kind), TypeValEnum::Int(_)) if Self::converts_implicitly(rhs, &lhs.typeval) => Some(*lhs_kind),
+            _ => None,
+        }
+    }
+
+    /// Kind of an arithmetic result, mismatched operands fall back to the left side (they are reported in `gen_expr`)
+    fn operand_kind(lhs: &CmplSymbol, rhs: &CmplSymbol) -> IntKind {
+        Self::unify_ints(lhs, rhs).unwrap_or(match lhs.typeval.as_enum() {
+            TypeValEnum::Int(kind) => *kind,
+            _ => IntKind::U64,
+        })
+    }
+
+    /// A shift keeps the kind of the shifted value, the amount can be of any kind
+    fn shifted_kind(lhs: &CmplSymbol) -> IntKind {
+        match lhs.typeval.as_enum() {
+            TypeValEnum::Int(kind) => *kind,
+            _ => IntKind::U64,
+        }
+    }
+
+    /// The amounts a value of `kind` can be shifted by without losing every bit
+    fn shift_range(kind: IntKind) -> Range<i128> {
+        0..(kind.size_of() * 8) as i128
+    }
+
+    /// An integer, as opposed to an integer type, which `|` would combine into a tagged union
+    fn is_int_value(symbol: &CmplSymbol) -> bool {
+        matches!(symbol.typeval.as_enum(), TypeValEnum::Int(..)) && !matches!(symbol.const_val, Some(CTimeVal::Type(..)))
+    }
+
+    fn check_shift_amount(&mut self, kind: IntKind, amount: &'a Expr) {
+        let amount_symbol = self.resolve_expr(amount);
+        match (amount_symbol.typeval.as_enum(), amount_symbol.const_val) {
+            (TypeValEnum::Int(..), Some(CTimeVal::Int(int))) if !Self::shift_range(kind).contains(&int) => {
+                let range = Self::shift_range(kind);
+                self.emit(
+                    Diagnostic::error(*amount.get_span(), "InvalidShift", format!("cannot shift `{kind}` by {int} bits").as_str())
+                        .with_note(format!("the amount must be between {} and {}", range.start, range.end - 1).as_str())
+                );
+            },
+            (TypeValEnum::Int(..), _) => (),
+            _ => self.emit_diagnostic(amount.get_span(), "InvalidShift", format!("shift amount must be an integer, but got `{}`", amount_symbol.typeval).as_str()),
+        }
+    }
+
+    fn check_int_operands(&mut self, operands: &'a (Expr, Expr), span: &Span) -> IntKind {
+        let lhs_symbol = self.resolve_expr(&operands.0);
+        let rhs_symbol = self.resolve_expr(&operands.1);
+
+        if let (TypeValEnum::Int(lhs_kind), TypeValEnum::Int(rhs_kind)) = (lhs_symbol.typeval.as_enum(), rhs_symbol.typeval.as_enum())
+            && Self::unify_ints(&lhs_symbol, &rhs_symbol).is_none() {
+            self.emit(
+                Diagnostic::error(*span, "MismatchedIntegers", format!("cannot mix `{lhs_kind}` and `{rhs_kind}`").as_str())
+                    .with_note("convert one side with `as`")
+            );
+        }
+
+        Self::operand_kind(&lhs_symbol, &rhs_symbol)
+    }
+
+    fn check_int_conversion(&mut self, symbol: &CmplSymbol, typeval: &TypeVal, span: &Span) {
+        if let (TypeValEnum::Int(from_kind), TypeValEnum::Int(kind)) = (symbol.typeval.as_enum(), typeval.as_enum())
+            && !Self::converts_implicitly(symbol, typeval) {
+            let message = match symbol.const_val {
+                Some(CTimeVal::Int(int)) => format!("`{int}` does not fit in `{kind}`"),
+                _ => format!("expected `{kind}`, but got `{from_kind}`"),
+            };
+            self.emit(
+                Diagnostic::error(*span, "ImplicitConversion", message.as_str())
+                    .with_note(format!("convert it explicitly with `as {kind}`").as_str())
+            );
+        } else if Self::byte_kind(&symbol.typeval).is_some() && Self::byte_kind(typeval).is_some()
+            && (symbol.typeval.t_enum == TypeValEnum::Char) != (typeval.t_enum == TypeValEnum::Char) {
+            // `char` and the integers never mix, not even `u8`
+            self.emit(
+                Diagnostic::error(*span, "ImplicitConversion", format!("expected `{typeval}`, but got `{}`", symbol.typeval).as_str())
+                    .with_note(format!("convert it explicitly with `as {typeval}`").as_str())
+            );
+        }
+    }
+
+    /// A pointer that may be null never becomes a plain pointer without a check
+    fn check_null_conversion(&mut self, symbol: &CmplSymbol, typeval: &TypeVal, span: &Span) {
+        if let (TypeValEnum::NullablePointer(..), TypeValEnum::Pointer(..)) = (symbol.typeval.as_enum(), typeval.as_enum()) {
+            let message = match symbol.const_val {
+                Some(CTimeVal::Int(0)) => format!("`null` is not a valid `{typeval}`"),
+                _ => format!("expected `{typeval}`, but got `{}`", symbol.typeval),
+            };
+            self.emit(
+                Diagnostic::error(*span, "NullablePointer", message.as_str())
+                    .with_note(format!("declare it as `?{typeval}`, or check it first with `if (value :: name {typeval})`").as_str())
+            );
+        }
+    }
+
+    /// The integer kind a value converts from with `as`, a `char` is a `u8`
+    fn byte_kind(typeval: &TypeVal) -> Option<IntKind> {
+        match typeval.as_enum() {
+            TypeValEnum::Int(kind) => Some(*kind),
+            TypeValEnum::Char => Some(IntKind::U8),
+            _ => None,
+        }
+    }
+
+    /// Brings a result back into the range of a narrow kind, words are kept zero or sign extended
+    fn gen_truncate(&mut self, kind: IntKind) {
+        if kind.size_of() < SIZE_64 {
+            self.emit_node(IRNode::Truncate64 { size: kind.size_of(), is_signed: kind.is_signed() });
+        }
+    }
+
+    fn condition_of(op: Operator) -> Condition {
+        match op {
+            Operator::Eq => Condition::Eq,
+            Operator::NotEq => Condition::NotEq,
+            Operator::Less => Condition::Less,
+            Operator::LessEq => Condition::LessEq,
+            Operator::Greater => Condition::Greater,
+            Operator::GreaterEq => Condition::GreaterEq,
+            _ => unreachable!("`{op:?}` is not a comparison"),
+        }
+    }
+
+    /// Only single word values can be compared, and only integers and `char` have an ordering
+    fn check_comparison(op: Operator, lhs_typeval: &TypeVal, rhs_typeval: &TypeVal) -> Result<(), String> {
+        // `null` has the type `?*void`, it compares with every nullable pointer
+        let is_null_check = match (lhs_typeval.as_enum(), rhs_typeval.as_enum()) {
+            (TypeValEnum::NullablePointer(lhs_sub), TypeValEnum::NullablePointer(rhs_sub)) => lhs_sub.t_enum == TypeValEnum::Unit || rhs_sub.t_enum == TypeValEnum::Unit,
+            _ => false,
+        };
+
+        if lhs_typeval != rhs_typeval && !is_null_check {
+            return Err(format!("cannot compare `{lhs_typeval}` with `{rhs_typeval}`"))
+        }
+
+        let is_ordering = !matches!(op, Operator::Eq | Operator::NotEq);
+        match lhs_typeval.as_enum() {
+            TypeValEnum::Int(_) | TypeValEnum::Char => Ok(()),
+
+            TypeValEnum::Bool
+            | TypeValEnum::Pointer(..)
+            | TypeValEnum::NullablePointer(..)
+            | TypeValEnum::FunctionPointer(..) if !is_ordering => Ok(()),
+
+            _ if is_ordering => Err(format!("type `{lhs_typeval}` has no ordering")),
+            _ => Err(format!("type `{lhs_typeval}` cannot be compared for equality")),
+        }
+    }
+
+    fn resolve_call(&mut self, symbol: &CmplSymbol, args: &'a Vec<Expr>, span: &Span) -> CmplSymbol {
         match &symbol.const_val {
-            Some(CTimeVal::DynamicFnDispatcher { map, meta_funcs: _ }) => {
-                let selected = map.get(&self.resolve_expr(args.first().unwrap()).typeval);
+            Some(CTimeVal::DynamicFnDispatcher { candidates, meta_funcs: _ }) => {
+                let matches = self.match_overloads(candidates, args);
                 // synthesize a call
-                if let Some(selected) = selected {
-                    self.resolve_call(selected, args)
+                if let [selected] = matches.as_slice() {
+                    self.resolve_call(selected, args, span)
                 } else {
                     CmplSymbol {
                         const_val: None,
@@ -1434,18 +4120,24 @@ impl<'a> IRGen<'a> {
             },
             
             _ => match symbol.typeval.as_enum() {
-                TypeValEnum::FunctionPointer(_param_typevals, return_typeval) => CmplSymbol {
-                    const_val: None,
-                    typeval: *return_typeval.clone(),
-                    var: None,
-                    lifetime: None,
-                    is_unsafe: false,
+                TypeValEnum::FunctionPointer(param_typevals, return_typeval)
+                | TypeValEnum::Closure(param_typevals, return_typeval, _)
+                | TypeValEnum::MethodPointer(param_typevals, return_typeval) => {
+                    self.check_args(param_typevals, args, span);
+                    CmplSymbol {
+                        const_val: None,
+                        typeval: *return_typeval.clone(),
+                        var: None,
+                        lifetime: None,
+                        is_unsafe: false,
+                    }
                 },
 
+                // constructing a tagged union makes a value, not a type
                 TypeValEnum::TaggedUnion(..) => {
                     let typeval = symbol.typeval.clone();
                     CmplSymbol {
-                        const_val: Some(CTimeVal::Type(typeval.clone())),
+                        const_val: None,
                         typeval: typeval,
                         var: None,
                         lifetime: None,
@@ -1476,6 +4168,8 @@ impl<'a> IRGen<'a> {
             // that is only declared inside this block
             // so we must rollback changes after
             let prev_ir_count = self.cprog.count_ir();
+            let prev_stack_sz = self.stack_sz;
+            let prev_symbol_cache = self.symbol_cache.clone();
 
             self.diagnostics_lock += 1;
             self.open_scope();
@@ -1493,6 +4187,8 @@ impl<'a> IRGen<'a> {
             self.close_scope();
             self.diagnostics_lock -= 1;
 
+            self.symbol_cache = prev_symbol_cache;
+            self.stack_sz = prev_stack_sz;
             self.cprog.shift_nodes(prev_ir_count..=(self.cprog.ir_pos()));
 
             symbol
@@ -1519,7 +4215,7 @@ impl<'a> IRGen<'a> {
 
 impl<'a> IRGen<'a> {
     fn push_zeroval(&mut self, typeval: &TypeVal) -> Result<(), String> {
-        self.stack_sz += typeval.size_of();
+        self.stack_sz += typeval.stack_size_of();
         match typeval.as_enum() {
             TypeValEnum::Pointer(..) => {
                 self.emit_node(IRNode::Push64(0));
@@ -1531,103 +4227,167 @@ impl<'a> IRGen<'a> {
                 Err(format!("no default for variant type `{typeval}`"))
             },
 
-            TypeValEnum::MethodPointer
+            TypeValEnum::MethodPointer(..)
             | TypeValEnum::StringSlice
+            | TypeValEnum::Slice(..)
             | TypeValEnum::FunctionPointer(..) => {
                 self.emit_node(IRNode::Push64(0));
                 self.emit_node(IRNode::Push64(0));
                 Err(format!("zeroval for `{typeval}` implies null pointers"))
             },
 
-            TypeValEnum::UInt64 => {
+            TypeValEnum::Closure(..) => {
+                for _ in 0..(typeval.stack_size_of() / SIZE_64) {
+                    self.emit_node(IRNode::Push64(0));
+                }
+                Err(format!("no zero value for closure `{typeval}`"))
+            },
+
+            // null is a valid value for these
+            TypeValEnum::Int(_) | TypeValEnum::Bool | TypeValEnum::Char | TypeValEnum::NullablePointer(..) => {
                 self.emit_node(IRNode::Push64(0));
                 Ok(())
             },
 
+            TypeValEnum::Class(class) => {
+                self.stack_sz -= typeval.stack_size_of(); // every field adds its own size
+
+                let mut result = Ok(());
+                for (field_name, field_typeval) in &class.fields {
+                    if self.push_zeroval(field_typeval).is_err() && result.is_ok() {
+                        result = Err(format!("zeroval not allowed for type `{typeval}` since one of its fields `{field_name}` is type `{field_typeval}`"));
+                    }
+                }
+                result
+            },
+
+            TypeValEnum::Array(element_typeval, len) => {
+                self.stack_sz -= typeval.stack_size_of(); // every element adds its own size
+
+                let mut result = Ok(());
+                for _ in 0..*len {
+                    if self.push_zeroval(element_typeval).is_err() && result.is_ok() {
+                        result = Err(format!("zeroval not allowed for type `{typeval}` since its elements are type `{element_typeval}`"));
+                    }
+                }
+                result
+            },
+
             TypeValEnum::Unit => Ok(()), // nothing to push
         }
     }
 
     fn pop_to_stack(&mut self, typeval: &TypeVal, offset: usize) {
         match typeval.as_enum() {
-            TypeValEnum::Pointer(..) => self.emit_node(IRNode::Pop64ToStack(offset)),
-            TypeValEnum::UInt64 => self.emit_node(IRNode::Pop64ToStack(offset)),
+            TypeValEnum::Pointer(..) | TypeValEnum::NullablePointer(..) => self.emit_node(IRNode::Pop64ToStack(offset)),
+            TypeValEnum::Int(_) | TypeValEnum::Bool | TypeValEnum::Char => self.emit_node(IRNode::Pop64ToStack(offset)),
             TypeValEnum::FunctionPointer(..) => self.emit_node(IRNode::Pop64ToStack(offset)),
-            TypeValEnum::MethodPointer | TypeValEnum::StringSlice => {
+            TypeValEnum::MethodPointer(..) | TypeValEnum::StringSlice | TypeValEnum::Slice(..) => {
                 self.emit_node(IRNode::Pop64ToStack(offset));
                 self.emit_node(IRNode::Pop64ToStack(offset));
             },
 
-            TypeValEnum::TaggedUnion(_typevals) => todo!(),
+            TypeValEnum::TaggedUnion(..) | TypeValEnum::Class(..) | TypeValEnum::Array(..) | TypeValEnum::Closure(..) => {
+                // tag is on top, every pop moves the stack pointer so the offset stays the same
+                for _ in 0..(typeval.stack_size_of() / SIZE_64) {
+                    self.emit_node(IRNode::Pop64ToStack(offset));
+                }
+            },
             TypeValEnum::Unit => {},
         }
 
-        self.stack_sz -= typeval.size_of();
+        self.stack_sz -= typeval.stack_size_of();
+    }
+
+    /// The reverse of `global_read_push`, the top word goes to the end of the global
+    fn pop_to_global(&mut self, typeval: &TypeVal, global_pos: usize) {
+        for i in (0..(typeval.stack_size_of() / SIZE_64)).rev() {
+            self.emit_node(IRNode::PopGlobal64(global_pos + i * SIZE_64));
+        }
+
+        self.stack_sz -= typeval.stack_size_of();
     }
 
     fn global_read_push(&mut self, typeval: &TypeVal, global_pos: usize) {
         match typeval.as_enum() {
-            TypeValEnum::UInt64
+            TypeValEnum::Int(_)
+            | TypeValEnum::Bool
+            | TypeValEnum::Char
             | TypeValEnum::FunctionPointer(..)
-            | TypeValEnum::Pointer(..) => {
+            | TypeValEnum::Pointer(..)
+            | TypeValEnum::NullablePointer(..) => {
                 self.emit_node(IRNode::GlobalReadPush64(global_pos));
             },
 
-            TypeValEnum::MethodPointer | TypeValEnum::StringSlice => {
+            TypeValEnum::MethodPointer(..) | TypeValEnum::StringSlice | TypeValEnum::Slice(..) => {
                 self.emit_node(IRNode::GlobalReadPush64(global_pos));
                 self.emit_node(IRNode::GlobalReadPush64(global_pos + 8));
             },
 
-            TypeValEnum::TaggedUnion(_typevals) => todo!(),
+            TypeValEnum::TaggedUnion(..) | TypeValEnum::Class(..) | TypeValEnum::Array(..) | TypeValEnum::Closure(..) => {
+                // stored in push order, so the tag is the last word
+                for i in 0..(typeval.stack_size_of() / SIZE_64) {
+                    self.emit_node(IRNode::GlobalReadPush64(global_pos + i * SIZE_64));
+                }
+            },
             TypeValEnum::Unit => {},
         }
 
-        self.stack_sz += typeval.size_of();
+        self.stack_sz += typeval.stack_size_of();
     }
 
-    fn external_read_push(&mut self, typeval: &TypeVal, external: ExternalInfo) {
+    fn external_read_push(&mut self, typeval: &TypeVal, external: ExternalInfo, span: &Span) {
         match typeval.as_enum() {
-            TypeValEnum::UInt64
+            TypeValEnum::Int(_)
+            | TypeValEnum::Bool
+            | TypeValEnum::Char
             | TypeValEnum::FunctionPointer(..)
-            | TypeValEnum::Pointer(..) => {
+            | TypeValEnum::Pointer(..)
+            | TypeValEnum::NullablePointer(..) => {
                 self.emit_node(IRNode::ExternalReadPush64(external));
             },
 
-            TypeValEnum::MethodPointer | TypeValEnum::StringSlice => {
-                self.emit_node(IRNode::ExternalReadPush64(external));
+            TypeValEnum::MethodPointer(..) | TypeValEnum::StringSlice | TypeValEnum::Slice(..)
+            | TypeValEnum::TaggedUnion(..) | TypeValEnum::Class(..) | TypeValEnum::Array(..) | TypeValEnum::Closure(..) => {
+                // only single words can be read through an external symbol,
+                // keep the stack balanced so codegen can continue
+                self.emit_diagnostic(span, "UnsupportedExternal", format!("reading `{typeval}` from package `{}` is not supported", external.package_name).as_str());
+                self.emit_node(IRNode::StackAlloc(typeval.stack_size_of()));
             },
-
-            TypeValEnum::TaggedUnion(_typevals) => todo!(),
             TypeValEnum::Unit => {},
         }
 
-        self.stack_sz += typeval.size_of();
+        self.stack_sz += typeval.stack_size_of();
     }
 
     fn stack_read_push(&mut self, typeval: &TypeVal, offset: usize) {
         match typeval.as_enum() {
-            TypeValEnum::UInt64
+            TypeValEnum::Int(_)
+            | TypeValEnum::Bool
+            | TypeValEnum::Char
             | TypeValEnum::FunctionPointer(..)
-            | TypeValEnum::Pointer(..) => {
+            | TypeValEnum::Pointer(..)
+            | TypeValEnum::NullablePointer(..) => {
                 self.emit_node(IRNode::StackReadPush64(offset));
             },
 
-            TypeValEnum::MethodPointer | TypeValEnum::StringSlice => {
+            TypeValEnum::MethodPointer(..) | TypeValEnum::StringSlice | TypeValEnum::Slice(..) => {
                 self.emit_node(IRNode::StackReadPush64(offset + 8));
                 self.emit_node(IRNode::StackReadPush64(offset + 8));
             },
 
-            TypeValEnum::TaggedUnion(typevals) => {
-                // for tagged unions we also need to push the tag
-                let greatest = TypeVal::greatest(typevals);
-                self.stack_read_push(&greatest, offset + /*tag*/ SIZE_64);
-                self.emit_node(IRNode::StackReadPush64(offset + 16)); // tag
+            TypeValEnum::TaggedUnion(..) | TypeValEnum::Class(..) | TypeValEnum::Array(..) | TypeValEnum::Closure(..) => {
+                // copy every word (including the tag), highest address first
+                let size = typeval.stack_size_of();
+                for _ in 0..(size / SIZE_64) {
+                    self.emit_node(IRNode::StackReadPush64(offset + size - SIZE_64));
+                }
             },
             
             TypeValEnum::Unit => {},
         }
 
-        self.stack_sz += typeval.size_of();
+        self.stack_sz += typeval.stack_size_of();
     }
 }
 
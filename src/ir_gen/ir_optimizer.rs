@@ -187,8 +187,9 @@ impl<'a> IROptimizer<'a> {
                 IRNode::PushAddressFromOffset(offset) => match self.cprog.node_clone_at(i+1) {
 
                     IRNode::Call => {
-                        self.cprog.shift_nodes(i..=(i+1));
-                        self.cprog.insert_node(i, IRNode::CallFromOffset(offset + 1));
+                        // replaced in place so the offset stays relative to this node
+                        *self.cprog.node_mut_at(i) = IRNode::CallFromOffset(offset);
+                        self.cprog.shift_nodes((i+1)..=(i+1));
                         optimize_count += 1;
                         continue
                     },
@@ -196,6 +197,14 @@ impl<'a> IROptimizer<'a> {
                     _ => {},
                 },
 
+                // fused compare and branch
+                IRNode::Cmp64(condition) => if let IRNode::JumpIfNot64FromOffset(offset) = self.cprog.node_clone_at(i+1) {
+                    *self.cprog.node_mut_at(i) = IRNode::JumpIfNotCmp64FromOffset(condition, offset + 1);
+                    self.cprog.shift_nodes((i+1)..=(i+1));
+                    optimize_count += 1;
+                    continue
+                },
+
                 IRNode::ExternalReadPush64(external) => match self.cprog.node_clone_at(i+1) {
 
                     IRNode::Call => {
@@ -0,0 +1,24 @@
+use crate::ir_gen::{lifetime::Lifetime, typeval::TypeVal};
+
+#[derive(Clone)]
+pub struct LoopInfo {
+    pub start_label: usize,
+    pub stack_base: usize, // stack size right before the loop, `break` and `continue` unwind to here
+    pub result: Option<TypeVal>, // unknown until the first `break` when resolving
+    pub is_while: bool,
+    pub breaks: Vec<usize>,
+    pub lifetime: Lifetime,
+}
+
+impl LoopInfo {
+    pub fn new(start_label: usize, stack_base: usize, result: Option<TypeVal>, is_while: bool, lifetime: Lifetime) -> Self {
+        Self {
+            start_label,
+            stack_base,
+            result,
+            is_while,
+            breaks: Vec::new(),
+            lifetime,
+        }
+    }
+}
@@ -12,3 +12,5 @@ pub mod typeval;
 pub mod external;
 pub mod lifetime;
 
+pub mod loop_info;
+pub mod class_info;
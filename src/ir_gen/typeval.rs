@@ -1,15 +1,24 @@
-use std::fmt::Display;
+use std::{fmt::Display, rc::Rc};
+
+use crate::{ir_gen::class_info::ClassInfo, parser::int_kind::IntKind};
 
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TypeValEnum {
     Unit,
     Pointer(Box<TypeVal>),
+    NullablePointer(Box<TypeVal>), // like `Pointer`, but 0 means null
     TaggedUnion(Vec<TypeVal>),
-    UInt64,
+    Int(IntKind),
+    Bool,
+    Char, // a single byte, like `u8` but printed as text
     StringSlice,
+    Array(Box<TypeVal>, usize), // (element, len)
+    Slice(Box<TypeVal>), // pointer and length, like `StringSlice`
     FunctionPointer(Vec<TypeVal>, Box<TypeVal>),
-    MethodPointer, // also contains a reference to 'self'
+    Closure(Vec<TypeVal>, Box<TypeVal>, Vec<TypeVal>), // (params, return, captures), the captures are followed by the function
+    MethodPointer(Vec<TypeVal>, Box<TypeVal>), // (params, return), also contains a reference to 'self' under the function
+    Class(Rc<ClassInfo>),
 }
 
 impl TypeValEnum { 
@@ -45,6 +54,16 @@ impl TypeVal {
         }
     }
     
+    /// Pointers and slices point into storage that has to outlive them, closures may hold such pointers
+    pub fn is_borrowed(&self) -> bool {
+        match &self.t_enum {
+            TypeValEnum::Pointer(..) | TypeValEnum::NullablePointer(..) | TypeValEnum::Slice(..) => true,
+            TypeValEnum::Closure(_, _, captures) => captures.iter().any(TypeVal::is_borrowed),
+            TypeValEnum::MethodPointer(..) => true,
+            _ => false,
+        }
+    }
+
     pub fn to_ptr(self) -> Self {
         TypeValEnum::Pointer(Box::new(self)).to_tval()
     }
@@ -56,14 +75,31 @@ impl TypeVal {
         }
     }
     
+    /// Size of the value itself, integers narrower than a word only count their own bytes
     pub fn size_of(&self) -> usize {
+        match &self.t_enum {
+            TypeValEnum::Int(kind) => kind.size_of(),
+            TypeValEnum::Char => 1,
+            _ => self.stack_size_of(),
+        }
+    }
+
+    /// Size the value takes on the stack (and in globals), always whole 64 bit words
+    pub fn stack_size_of(&self) -> usize {
         match &self.t_enum {
             TypeValEnum::Unit => 0,
             TypeValEnum::Pointer(..) => 8,
-            TypeValEnum::UInt64 => 8,
+            TypeValEnum::NullablePointer(..) => 8,
+            TypeValEnum::Int(..) => 8,
+            TypeValEnum::Bool => 8, // a full word, the stack only deals in 64 bit values
+            TypeValEnum::Char => 8,
             TypeValEnum::StringSlice => 16,
+            TypeValEnum::Array(element, len) => element.stack_size_of() * len,
+            TypeValEnum::Slice(..) => 16,
             TypeValEnum::FunctionPointer(..) => 8,
-            TypeValEnum::MethodPointer => 16,
+            TypeValEnum::Closure(_, _, captures) => captures.iter().map(TypeVal::stack_size_of).sum::<usize>() + 8,
+            TypeValEnum::MethodPointer(..) => 16,
+            TypeValEnum::Class(class) => class.stack_size_of(),
 
             TypeValEnum::TaggedUnion(typevals) => {
                 // LAYOUT
@@ -79,9 +115,9 @@ impl TypeVal {
     pub fn greatest(typevals: &Vec<Self>) -> Self {
         let mut greatest: Option<&Self> = None;
         for typeval in typevals {
-            let size = typeval.size_of();
+            let size = typeval.stack_size_of();
             if let Some(cur_greatest) = greatest {
-                if size > cur_greatest.size_of() {
+                if size > cur_greatest.stack_size_of() {
                     greatest = Some(typeval);
                 }
             } else {
@@ -94,7 +130,7 @@ impl TypeVal {
 
     pub fn greatest_size(typevals: &Vec<Self>) -> usize {
         let greatest = Self::greatest(typevals);
-        greatest.size_of()
+        greatest.stack_size_of()
     }
 }
 
@@ -103,15 +139,52 @@ impl Display for TypeVal {
         let typeval = self;
         match typeval.as_enum() {
             TypeValEnum::Unit => write!(f, "void"),
-            TypeValEnum::UInt64 => write!(f, "u64"),
+            TypeValEnum::Int(kind) => write!(f, "{kind}"),
+            TypeValEnum::Bool => write!(f, "bool"),
+            TypeValEnum::Char => write!(f, "char"),
             TypeValEnum::StringSlice => write!(f, "str"),
-            TypeValEnum::FunctionPointer(..) => write!(f, "(...)"),
-            TypeValEnum::MethodPointer => write!(f, "(self, ...)"),
+            TypeValEnum::Array(element, len) => write!(f, "[{len}]{element}"),
+            TypeValEnum::Slice(element) => write!(f, "[]{element}"),
+            TypeValEnum::FunctionPointer(param_typevals, return_typeval) => {
+                write!(f, "(")?;
+
+                for (i, typeval) in param_typevals.iter().enumerate() {
+                    if (i + 1) >= param_typevals.len() {
+                        write!(f, "{typeval}")?;
+                    } else {
+                        write!(f, "{typeval}, ")?;
+                    }
+                }
+
+                match return_typeval.as_enum() {
+                    TypeValEnum::Unit => write!(f, ")"),
+                    _ => write!(f, ") :: {return_typeval}"),
+                }
+            },
+            TypeValEnum::Closure(param_typevals, return_typeval, captures) => {
+                let function = TypeValEnum::FunctionPointer(param_typevals.clone(), return_typeval.clone()).to_tval();
+                let captures: Vec<String> = captures.iter().map(TypeVal::to_string).collect();
+                write!(f, "{function} {{{}}}", captures.join(", "))
+            },
+            TypeValEnum::MethodPointer(param_typevals, return_typeval) => {
+                write!(f, "(self")?;
+                for typeval in param_typevals {
+                    write!(f, ", {typeval}")?;
+                }
+
+                match return_typeval.as_enum() {
+                    TypeValEnum::Unit => write!(f, ")"),
+                    _ => write!(f, ") :: {return_typeval}"),
+                }
+            },
+            TypeValEnum::Class(class) => write!(f, "{}", class.name.as_deref().unwrap_or("class")),
 
             TypeValEnum::Pointer(sub_typeval) => {
                 write!(f, "*{sub_typeval}")
             },
 
+            TypeValEnum::NullablePointer(sub_typeval) => write!(f, "?*{sub_typeval}"),
+
             TypeValEnum::TaggedUnion(typevals) => {
                 write!(f, "(")?;
                 
@@ -6,6 +6,7 @@ pub struct Variable {
     pub typeval: TypeVal,
     pub global_pos: Option<usize>,
     pub stack_loc: Option<usize>,
+    pub indirect: Option<(usize, usize)>, // (stack location of its address, offset from it), for variables captured by reference
     pub const_val: Option<CTimeVal>,
     pub external: Option<ExternalInfo>,
     pub is_alias: bool,
@@ -1,27 +1,33 @@
-use std::fs;
+use std::rc::Rc;
 
-use crate::lexer::{token_map::TokenMap, tokens::{SourceLocation, TokenEnum, Tokens}};
+use crate::{diagnostics::{diagnostic::Diagnostic, emitter::DiagnosticEmitter, source_file::SourceFile}, lexer::{token_map::TokenMap, tokens::{SourceLocation, Span, TokenEnum, Tokens}}};
 
 
 pub struct Lexer {
     input: Vec<char>,
     pos: usize,
     loc: SourceLocation,
+    diag: DiagnosticEmitter,
 }
 
 impl Lexer {
-    pub fn from_file(file_name: &str) -> Self {
-        let file_data = fs::read_to_string(file_name).unwrap_or_else(|_| {
-            println!("{file_name}");
-            "public main = () { print (\"Hello, furn!\") }".to_string()
-        }).chars().collect();
+    pub fn new(source: Rc<SourceFile>, diag: DiagnosticEmitter) -> Self {
         Self {
-            input: file_data,
+            input: source.get_text().chars().collect(),
             pos: 0,
             loc: SourceLocation::new(1, 1),
+            diag,
         }
     }
 
+    pub fn has_errors(&self) -> bool {
+        self.diag.has_errors()
+    }
+
+    fn emit_diagnostic(&mut self, span: Span, code: &'static str, message: &str) {
+        self.diag.emit(Diagnostic::error(span, code, message));
+    }
+
     pub fn tokenize<TokT: Clone>(&mut self, token_map: TokenMap<TokT>) -> Tokens<TokT> {
         let mut tokens = Tokens::new();
         while !self.is_eof() {
@@ -29,15 +35,30 @@ impl Lexer {
             if self.is_space() {
                 self.advance();
             } else if self.peek() == '"' {
-                let text = self.lex_quoted();
-                tokens.push(TokenEnum::StringLiteral(text).to_tok(loc));
+                if let Some(text) = self.lex_quoted() {
+                    tokens.push(TokenEnum::StringLiteral(text).to_tok(self.span_from(loc)));
+                } else {
+                    tokens.push(TokenEnum::Error.to_tok(self.span_from(loc)));
+                }
+            } else if self.peek() == '\'' {
+                if let Some(text) = self.lex_char() {
+                    tokens.push(TokenEnum::CharLiteral(text).to_tok(self.span_from(loc)));
+                } else {
+                    tokens.push(TokenEnum::Error.to_tok(self.span_from(loc)));
+                }
             } else if self.peek() == '#' {
                 self.advance();
-                if self.peek() == '{' {
+                if !self.is_eof() && self.peek() == '{' {
                     self.advance();
                     
                     let mut brace_nest_level = 0;
                     loop {
+                        if self.is_eof() {
+                            let span = Span::new(loc, SourceLocation::new(loc.line, loc.col + 2));
+                            self.emit_diagnostic(span, "UnterminatedComment", "unterminated block comment, expected `}`");
+                            break
+                        }
+
                         let ch = self.advance();
                         if ch == '{' {
                             brace_nest_level += 1;
@@ -50,30 +71,40 @@ impl Lexer {
                         }
                     }
                 } else {
-                    while self.advance() != '\n' {}
+                    while !self.is_eof() && self.advance() != '\n' {}
+                }
+            } else if self.is_raw_string_start() {
+                if let Some(text) = self.lex_raw_quoted() {
+                    tokens.push(TokenEnum::StringLiteral(text).to_tok(self.span_from(loc)));
+                } else {
+                    tokens.push(TokenEnum::Error.to_tok(self.span_from(loc)));
                 }
             } else if self.is_alpha() {
                 let ident = self.lex_ident();
                 let token_keyword = self.map_to_keyword(&token_map, &ident);
 
                 if let Some(other) = token_keyword {
-                    tokens.push(TokenEnum::from_other(other).to_tok(loc));
+                    tokens.push(TokenEnum::from_other(other).to_tok(self.span_from(loc)));
                 } else {
-                    tokens.push(TokenEnum::Ident(ident).to_tok(loc));
+                    tokens.push(TokenEnum::Ident(ident).to_tok(self.span_from(loc)));
                 }
             } else if self.is_digit() {
-                let int = self.lex_int();
-                tokens.push(TokenEnum::IntLiteral(int).to_tok(loc));
+                if let Some(int) = self.lex_int() {
+                    tokens.push(TokenEnum::IntLiteral(int).to_tok(self.span_from(loc)));
+                } else {
+                    let span = self.span_from(loc);
+                    self.emit_diagnostic(span, "IntLiteralTooLarge", "integer literal does not fit in `u64`");
+                    tokens.push(TokenEnum::Error.to_tok(span));
+                }
             } else {
                 match self.map_to_token(&token_map) {
-                    Some(token_other) => tokens.push(TokenEnum::from_other(token_other).to_tok(loc)),
+                    Some(token_other) => tokens.push(TokenEnum::from_other(token_other).to_tok(self.span_from(loc))),
                     None => {
-                        if self.is_eof() {
-                            panic!("Unexpected end of file at position {}", self.pos);
-                        } else {
-                            let ch = self.peek();
-                            panic!("Unexpected character '{ch}' at position {}", self.pos);
-                        }
+                        // skip it and keep lexing so every bad character gets reported
+                        let ch = self.advance();
+                        let span = self.span_from(loc);
+                        self.emit_diagnostic(span, "UnexpectedCharacter", format!("unexpected character `{}`", ch.escape_debug()).as_str());
+                        tokens.push(TokenEnum::Error.to_tok(span));
                     }
                 }
             }
@@ -105,6 +136,10 @@ impl Lexer {
         token_map.get(&key).cloned()
     }
 
+    fn span_from(&self, start: SourceLocation) -> Span {
+        Span::new(start, self.loc)
+    }
+
     fn is_eof(&self) -> bool {
         self.pos >= self.input.len()
     }
@@ -144,28 +179,148 @@ impl Lexer {
         !self.is_eof() && self.peek().is_ascii_digit()
     }
 
-    fn lex_quoted(&mut self) -> String {
+    /// `r"` or `r#...#"`
+    fn is_raw_string_start(&self) -> bool {
+        if self.is_eof() || self.peek() != 'r' {
+            return false
+        }
+
+        let rest = &self.input[(self.pos + 1)..];
+        let hashes = rest.iter().take_while(|ch| **ch == '#').count();
+        rest.get(hashes) == Some(&'"')
+    }
+
+    /// Returns `None` if the literal is never closed
+    fn lex_quoted(&mut self) -> Option<String> {
+        let start = self.loc;
         let quote = self.advance();
 
         let mut text = String::new();
-        while self.peek() != quote {
-            let ch = self.advance();
-            if ch == '\\' {
-
-                let escaped_ch = match self.advance() {
-                    '\\' => '\\',
-                    'n' => '\n',
-                    ch => ch,
-                };
+        loop {
+            if self.is_eof() {
+                let span = Span::point(start);
+                self.emit_diagnostic(span, "UnterminatedString", format!("unterminated string literal, expected closing `{quote}`").as_str());
+                return None
+            } else if self.peek() == quote {
+                break
+            }
 
-                text.push(escaped_ch);
+            let escape_start = self.loc;
+            let ch = self.advance();
+            if ch == '\\' && !self.is_eof() {
+                // invalid escapes are reported and left out, the rest of the literal is still checked
+                if let Some(escaped_ch) = self.lex_escape(escape_start) {
+                    text.push(escaped_ch);
+                }
             } else {
                 text.push(ch);
             }
         }
 
         self.advance();
-        text
+        Some(text)
+    }
+
+    /// Reads an escape sequence after its `\`, returns `None` if it is invalid
+    fn lex_escape(&mut self, start: SourceLocation) -> Option<char> {
+        let escaped_ch = match self.advance() {
+            '\\' => '\\',
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\'' => '\'',
+            '"' => '"',
+            'x' => {
+                let mut digits = String::new();
+                while digits.len() < 2 && !self.is_eof() && self.peek().is_ascii_hexdigit() {
+                    digits.push(self.advance());
+                }
+
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte.is_ascii() => byte as char,
+                    Ok(_) if digits.len() == 2 => {
+                        let span = self.span_from(start);
+                        self.emit_diagnostic(span, "InvalidEscape", format!("escape `\\x{digits}` is out of range, only `\\x00` to `\\x7f` are allowed").as_str());
+                        return None
+                    },
+                    _ => {
+                        let span = self.span_from(start);
+                        self.emit_diagnostic(span, "InvalidEscape", "`\\x` must be followed by two hex digits");
+                        return None
+                    },
+                }
+            },
+            ch => {
+                let span = self.span_from(start);
+                self.emit_diagnostic(span, "InvalidEscape", format!("unknown escape sequence `\\{}`", ch.escape_debug()).as_str());
+                return None
+            },
+        };
+
+        Some(escaped_ch)
+    }
+
+    /// Raw strings take everything up to `"` followed by as many `#` as they were opened with, escapes included
+    fn lex_raw_quoted(&mut self) -> Option<String> {
+        let start = self.loc;
+        self.advance(); // `r`
+
+        let mut closing = vec!['"'];
+        while self.peek() == '#' {
+            self.advance();
+            closing.push('#');
+        }
+        self.advance(); // `"`
+
+        let mut text = String::new();
+        loop {
+            if self.is_eof() {
+                let span = Span::point(start);
+                let closing: String = closing.iter().collect();
+                self.emit_diagnostic(span, "UnterminatedString", format!("unterminated raw string literal, expected closing `{closing}`").as_str());
+                return None
+            } else if self.input[self.pos..].starts_with(&closing) {
+                break
+            }
+
+            text.push(self.advance());
+        }
+
+        for _ in 0..closing.len() {
+            self.advance();
+        }
+        Some(text)
+    }
+
+    /// Returns `None` if the literal is never closed or is not exactly one ASCII character
+    fn lex_char(&mut self) -> Option<String> {
+        let start = self.loc;
+        let err_count = self.diag.err_count();
+        let text = self.lex_quoted()?;
+        if self.diag.err_count() > err_count {
+            return None // an invalid escape, already reported
+        }
+
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) if ch.is_ascii() => Some(text),
+            (Some(ch), None) => {
+                let span = self.span_from(start);
+                self.emit_diagnostic(span, "InvalidCharLiteral", format!("`{ch}` does not fit in a `char`, which holds a single byte").as_str());
+                None
+            },
+            (None, _) => {
+                let span = self.span_from(start);
+                self.emit_diagnostic(span, "InvalidCharLiteral", "empty character literal");
+                None
+            },
+            _ => {
+                let span = self.span_from(start);
+                self.emit_diagnostic(span, "InvalidCharLiteral", "character literal may only contain one character (use `\"` for strings)");
+                None
+            },
+        }
     }
 
     fn lex_ident(&mut self) -> String {
@@ -178,13 +333,14 @@ impl Lexer {
         text
     }
 
-    fn lex_int(&mut self) -> u64 {
+    /// Returns `None` if the literal does not fit in a `u64`, the digits are consumed either way
+    fn lex_int(&mut self) -> Option<u64> {
         let mut text = String::new();
         while self.is_digit() {
             let ch = self.advance();
             text.push(ch);
         }
 
-        text.parse().unwrap()
+        text.parse().ok()
     }
 }
@@ -10,6 +10,7 @@ pub enum TokenEnum<T> {
     IntLiteral(u64),
     FloatLiteral(f64),
     Other(T),
+    Error, // already reported by the lexer
 }
 
 impl<T> TokenEnum<T>
@@ -18,17 +19,17 @@ where T: Clone {
         TokenEnum::Other(other)
     }
 
-    pub fn to_tok(self, loc: SourceLocation) -> Token<T> {
+    pub fn to_tok(self, span: Span) -> Token<T> {
         Token {
             t_enum: self,
-            loc,
+            span,
         }
     }
 }
 
 pub struct Token<T> {
     t_enum: TokenEnum<T>,
-    loc: SourceLocation,
+    span: Span,
 }
 
 impl<T> Token<T> {
@@ -37,7 +38,11 @@ impl<T> Token<T> {
     }
 
     pub fn get_loc(&self) -> SourceLocation {
-        self.loc
+        self.span.start
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 }
 
@@ -51,11 +56,12 @@ where T: fmt::Display {
             TokenEnum::IntLiteral(int) => write!(f, "`{int}`"),
             TokenEnum::FloatLiteral(float) => write!(f, "`{float}`"),
             TokenEnum::Other(x) => write!(f, "'{x}'"),
+            TokenEnum::Error => write!(f, "<error>"),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SourceLocation {
     pub line: isize,
     pub col: isize,
@@ -74,6 +80,34 @@ impl SourceLocation {
     }
 }
 
+/// Start is inclusive, end is exclusive (the location right after the last char)
+#[derive(Clone, Copy, Debug)]
+pub struct Span {
+    pub start: SourceLocation,
+    pub end: SourceLocation,
+}
+
+impl Span {
+    pub fn new(start: SourceLocation, end: SourceLocation) -> Self {
+        Self {
+            start,
+            end,
+        }
+    }
+
+    pub fn point(loc: SourceLocation) -> Self {
+        Self::new(loc, SourceLocation::new(loc.line, loc.col + 1))
+    }
+
+    pub fn garbage() -> Self {
+        Self::point(SourceLocation::garbage())
+    }
+
+    pub fn to(&self, other: &Span) -> Self {
+        Self::new(self.start, other.end)
+    }
+}
+
 pub struct Tokens<T> {
     vec: Vec<Token<T>>,
 }
@@ -1,8 +1,9 @@
 
-use std::{env::{self, args}, fs::{self, File}, io::{BufWriter, Write}, process::{Command, Stdio}, time::Instant, hint::black_box};
+use std::{collections::HashMap, env::args, fs::{self, File}, io::{BufWriter, Write}, path::{Path, PathBuf}, process::{Command, Stdio}, time::Instant, hint::black_box};
 
-use crate::{flags::{CompilationTarget, Flags}, ir_gen::{cmpld_program::CompiledProgram, ir_gen::IRGen, ir_optimizer::IROptimizer}, lexer::{lexer::Lexer, tokens::Tokens}, maybe_inf::MaybeInf, outputs::asm_x86_64::gen_asm_x86_64_from_ir, parser::parser::Parser, tok::token_other::TokenOther};
+use crate::{diagnostics::{emitter::DiagnosticEmitter, source_file::SourceFile}, flags::{CompilationTarget, ErrorFormat, Flags}, ir_gen::{cmpld_program::CompiledProgram, ir_gen::IRGen, ir_optimizer::IROptimizer}, lexer::{lexer::Lexer, tokens::Tokens}, maybe_inf::MaybeInf, outputs::{asm_x86_64::gen_asm_x86_64_from_ir, interface::gen_interface_from_globals}, parser::{ast::{Stmt, StmtEnum}, parser::Parser}, tok::token_other::TokenOther};
 pub mod flags;
+pub mod diagnostics;
 pub mod maybe_inf;
 pub mod lexer;
 pub mod tok;
@@ -10,24 +11,124 @@ pub mod parser;
 pub mod ir_gen;
 pub mod outputs;
 
-fn clear_line() {
-    print!("\x1B[1A"); // move cursor up 1 line
-    print!("\x1B[2K"); // clear entire line
+/// Progress output, in json mode stdout only carries diagnostics so it goes to stderr instead
+macro_rules! status {
+    ($flags:expr, $($arg:tt)*) => {
+        match $flags.error_format {
+            ErrorFormat::Human => println!($($arg)*),
+            ErrorFormat::Json => eprintln!($($arg)*),
+        }
+    };
+}
+
+fn clear_line(flags: &Flags) {
+    if flags.error_format == ErrorFormat::Human {
+        print!("\x1B[1A"); // move cursor up 1 line
+        print!("\x1B[2K"); // clear entire line
+    }
 }
 
 #[allow(dead_code)]
-fn clear_lines(n: usize) {
+fn clear_lines(flags: &Flags, n: usize) {
     for _ in 0..n {
-        clear_line();
+        clear_line(flags);
     }
 }
 
-fn output_program(cprog: &CompiledProgram, target: CompilationTarget, flags: &Flags) {
-    let mut out_dir = env::temp_dir();
+/// Where the assembler and linker write their output
+fn tool_stdout(flags: &Flags) -> Stdio {
+    match flags.error_format {
+        ErrorFormat::Human => Stdio::inherit(),
+        ErrorFormat::Json => std::io::stderr().into(),
+    }
+}
+
+fn obj_extension(target: &CompilationTarget) -> &'static str {
+    match target {
+        CompilationTarget::LinuxX86_64 => "o",
+        CompilationTarget::Windows => "obj",
+        _ => unreachable!(),
+    }
+}
+
+/// The program being built, named after the first file unless given explicitly
+fn output_stem(flags: &Flags) -> String {
+    let first_file_name = flags.file_names.first().cloned().unwrap_or("unnamed".to_string());
+    flags.output_file_name.clone().unwrap_or_else(|| {
+        first_file_name
+        .rsplitn(2, ".").last().unwrap_or(first_file_name.as_str())
+        .to_string()
+    })
+}
+
+/// Objects and interface files live next to the program they are built for,
+/// so separate builds never read each other's packages
+fn artifacts_dir(flags: &Flags) -> PathBuf {
+    let output_path = PathBuf::from(output_stem(flags));
+    let mut out_dir = output_path.parent().map(Path::to_path_buf).unwrap_or_default();
     out_dir.push("furn-build-artifacts");
-    _ = fs::create_dir(&out_dir);
-    
-    let out_file = File::create(out_dir.join("out.asm")).unwrap();
+    out_dir
+}
+
+fn interface_path(package_name: &str, flags: &Flags) -> PathBuf {
+    artifacts_dir(flags).join(format!("{package_name}.fni"))
+}
+
+/// The text of `<package>.fni`, listing everything the package exports
+fn interface_text(cprog: &CompiledProgram) -> String {
+    let mut buffer = Vec::new();
+    gen_interface_from_globals(&mut buffer, cprog).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+/// Writes `<package>.fni` next to the objects, so later builds can import the package
+fn write_interface(package_name: &str, interface: &str, flags: &Flags) {
+    _ = fs::create_dir_all(artifacts_dir(flags));
+    fs::write(interface_path(package_name, flags), interface).unwrap();
+}
+
+/// Parses the interfaces of every imported package, missing ones are reported during IR generation,
+/// packages compiled earlier in this run are used even if nothing was written for them
+fn load_interfaces(ast: &[Stmt], flags: &Flags, compiled: &HashMap<String, String>) -> Vec<Vec<Stmt>> {
+    let mut interfaces = Vec::new();
+
+    for stmt in ast {
+        let StmtEnum::ImportDecl(package_name, _) = stmt.as_enum() else { continue };
+
+        let path = interface_path(package_name, flags);
+        let text = match compiled.get(package_name) {
+            Some(text) => text.clone(),
+            None => match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(_) => continue,
+            },
+        };
+        let source = SourceFile::new(path.to_string_lossy().to_string(), text);
+
+        let token_map = TokenOther::make_token_map();
+        let mut lexer = Lexer::new(source.clone(), DiagnosticEmitter::new(source.clone(), flags.error_format));
+        let tokens: Tokens<TokenOther> = lexer.tokenize(token_map);
+
+        let mut parser = Parser::new(&tokens, DiagnosticEmitter::new(source, flags.error_format));
+        let interface = parser.parse();
+
+        if !lexer.has_errors() && !parser.has_errors() {
+            interfaces.push(interface);
+        }
+    }
+
+    interfaces
+}
+
+/// Assembles a single package into `<package>.o`, returns false on assembler errors
+fn assemble_package(cprog: &CompiledProgram, target: &CompilationTarget, flags: &Flags) -> bool {
+    let out_dir = artifacts_dir(flags);
+    _ = fs::create_dir_all(&out_dir);
+    let package_name = cprog.get_package_name().unwrap_or("Main");
+    let asm_file = out_dir.join(format!("{package_name}.asm"));
+    let obj_file = out_dir.join(format!("{package_name}.{}", obj_extension(target)));
+
+    let out_file = File::create(&asm_file).unwrap();
     let mut buffer = BufWriter::new(out_file);
     
     match target {
@@ -38,47 +139,36 @@ fn output_program(cprog: &CompiledProgram, target: CompilationTarget, flags: &Fl
 
     buffer.flush().unwrap();
 
-    let obj_file = match target {
-        CompilationTarget::LinuxX86_64 => "out.o",
-        CompilationTarget::Windows => "out.obj",
-        _ => unreachable!(),
-    };
-
     let mut assembler = Command::new("nasm");
-    assembler.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    assembler.stdout(tool_stdout(flags)).stderr(Stdio::inherit());
     assembler.arg(match target {
         CompilationTarget::LinuxX86_64 => "-felf64",
         CompilationTarget::Windows => "-fwin64",
         _ => unreachable!(),
     });
-    assembler.arg(out_dir.join("out.asm")).arg("-o".to_string() + out_dir.join(obj_file).to_str().unwrap());
+    assembler.arg(asm_file).arg("-o".to_string() + obj_file.to_str().unwrap());
 
     match assembler.output().expect("Assembler command failed to start (make sure you have NASM installed)").status.code() {
-        Some(0) => {
-            clear_line();
-            println!(":: Linking object files...")
-        },
+        Some(0) => true,
         _ => {
-            println!(":: Assembler errors ^^^^^^^^^^^^^^^^^^^^");
-            return
+            status!(flags, ":: Assembler errors ^^^^^^^^^^^^^^^^^^^^");
+            false
         },
     }
+}
+
+/// Links the runtime and the objects of every package together
+fn link_packages(package_names: &[String], target: &CompilationTarget, flags: &Flags) {
+    let out_dir = artifacts_dir(flags);
 
     let mut linker = Command::new(match target {
         CompilationTarget::LinuxX86_64 => "ld",
         CompilationTarget::Windows => "gcc",
         _ => unreachable!(),
     });
-    linker.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    linker.stdout(tool_stdout(flags)).stderr(Stdio::inherit());
     
-    let output_path = flags.output_file_name.clone().unwrap_or_else(|| {
-        flags.file_name.clone()
-        .unwrap_or("unnamed".to_string())
-        .rsplitn(2, ".").last().unwrap_or(
-            flags.file_name.clone()
-            .unwrap_or_default().as_str()
-        ).to_string()
-    }) + match target {
+    let output_path = output_stem(flags) + match target {
         CompilationTarget::LinuxX86_64 => None,
         CompilationTarget::Windows => Some(".exe"),
         _ => unreachable!(),
@@ -90,52 +180,83 @@ fn output_program(cprog: &CompiledProgram, target: CompilationTarget, flags: &Fl
         _ => unreachable!(),
     };
     
-    linker.arg(runtime_file).arg(out_dir.join(obj_file)).arg("-o").arg(output_path);
+    linker.arg(runtime_file);
+    for package_name in package_names {
+        linker.arg(out_dir.join(format!("{package_name}.{}", obj_extension(target))));
+    }
+    linker.arg("-o").arg(output_path);
 
     match linker.output().expect("Linker command failed to start").status.code() {
         Some(0) => {
-            clear_line();
-            println!(":: Build complete.")
+            clear_line(flags);
+            status!(flags, ":: Build complete.")
         },
-        _ => println!(":: Linker errors (make sure the Furn Runtime is compiled) ^^^^^^^^^^^^^^^^^^^^"),
+        _ => status!(flags, ":: Linker errors (make sure the Furn Runtime is compiled) ^^^^^^^^^^^^^^^^^^^^"),
     }
 
-    clear_line();
+    clear_line(flags);
 }
 
-
-fn compile(flags: &Flags) {
+/// Compiles a single file down to IR and assembles it if a target was requested,
+/// returns the name of the package on success
+fn compile_package(file_name: &str, flags: &Flags, compiled: &mut HashMap<String, String>) -> Option<String> {
     let start = Instant::now();
 
-    println!(":: Lexing...");
+    status!(flags, ":: Lexing {file_name}...");
+
+    let source = SourceFile::from_file(file_name);
 
     let token_map = TokenOther::make_token_map();
-    let mut lexer = Lexer::from_file(flags.file_name.as_ref().unwrap().as_str());
+    let mut lexer = Lexer::new(source.clone(), DiagnosticEmitter::new(source.clone(), flags.error_format));
     let tokens: Tokens<TokenOther> = lexer.tokenize(token_map);
 
-    clear_line();
-    println!(":: Parsing...");
+    if lexer.has_errors() {
+        status!(flags, ":: Lex errors, aborting.");
+        return None
+    }
 
-    let mut parser = Parser::new(&tokens);
+    clear_line(flags);
+    status!(flags, ":: Parsing {file_name}...");
+
+    let mut parser = Parser::new(&tokens, DiagnosticEmitter::new(source.clone(), flags.error_format));
     let ast = parser.parse();
     
     if parser.has_errors() {
-        println!(":: Parse errors, aborting.");
-        return
+        status!(flags, ":: Parse errors, aborting.");
+        return None
     }
 
-    clear_line();
-    println!(":: Generating IR...");
+    clear_line(flags);
+    status!(flags, ":: Generating IR for {file_name}...");
+
+    let interfaces = load_interfaces(&ast, flags, compiled);
+
+    let mut ir_gen = IRGen::new(DiagnosticEmitter::new(source, flags.error_format));
+    for interface in &interfaces {
+        for stmt in interface {
+            if let StmtEnum::ImportDecl(package_name, Some(decls)) = stmt.as_enum() {
+                ir_gen.add_interface(package_name.clone(), decls);
+            }
+        }
+    }
 
-    let mut ir_gen = IRGen::new();
     let mut cprog = ir_gen.generate(&ast).clone();
 
     if ir_gen.has_errors() {
-        return
+        return None
     }
 
-    clear_line();
-    println!(":: Optimizing IR...");
+    // IR-only runs leave the artifacts alone, the rest of the run still sees the interface
+    if let Some(package_name) = cprog.get_package_name() {
+        let interface = interface_text(&cprog);
+        if !matches!(flags.target, Some(CompilationTarget::None)) {
+            write_interface(package_name, &interface, flags);
+        }
+        compiled.insert(package_name.to_string(), interface);
+    }
+
+    clear_line(flags);
+    status!(flags, ":: Optimizing IR...");
 
     let mut ir_optimizer = IROptimizer::new(&mut cprog);
     let cprog = match &flags.target {
@@ -148,45 +269,87 @@ fn compile(flags: &Flags) {
     };
 
     if flags.print_ir {
-        clear_line();
+        clear_line(flags);
         for node in cprog.ir_iter() {
-            println!("{node:?} ");
+            status!(flags, "{node:?} ");
         }
 
         // balance for clear_line
-        println!();
+        status!(flags, "");
     }
 
+    let package_name = cprog.get_package_name().unwrap_or("Main").to_string();
+
     let duration = start.elapsed();
-    clear_line();
+    clear_line(flags);
 
-    if let Some(target) = &flags.target {
-        match target {
-            CompilationTarget::None => {
-                println!(":: IR compilation took {}ms to complete.", duration.as_millis());
-            },
-            _ => {
-                println!(":: IR compilation took {}ms to complete. Assembling...", duration.as_millis());
-                output_program(cprog, target.clone(), &flags)
-            },
+    let target = flags.target.clone().unwrap_or(CompilationTarget::LinuxX86_64);
+    match target {
+        CompilationTarget::None => {
+            status!(flags, ":: IR compilation took {}ms to complete.", duration.as_millis());
+        },
+        _ => {
+            status!(flags, ":: IR compilation took {}ms to complete. Assembling...", duration.as_millis());
+            if !assemble_package(cprog, &target, flags) {
+                return None
+            }
+            clear_line(flags);
+        },
+    }
+
+    Some(package_name)
+}
+
+fn compile(flags: &Flags) {
+    let mut package_names: Vec<String> = Vec::new();
+    let mut interfaces: HashMap<String, String> = HashMap::new();
+    let mut has_errors = false;
+
+    // every file is compiled on its own, packages only meet at link time
+    for file_name in &flags.file_names {
+        let Some(package_name) = compile_package(file_name, flags, &mut interfaces) else {
+            has_errors = true;
+            continue
+        };
+
+        if package_names.contains(&package_name) {
+            status!(flags, ":: Package `{package_name}` is declared by more than one file ({file_name}), aborting.");
+            return
         }
-    } else {
-        println!(":: IR compilation took {}ms to complete. Building...", duration.as_millis());
-        output_program(cprog, CompilationTarget::LinuxX86_64, &flags);
+
+        package_names.push(package_name);
+    }
+
+    if has_errors {
+        return
+    }
+
+    match flags.target.clone().unwrap_or(CompilationTarget::LinuxX86_64) {
+        CompilationTarget::None => {},
+        target => {
+            status!(flags, ":: Linking object files...");
+            link_packages(&package_names, &target, flags);
+        },
     }
 }
 
 fn main() {
     let mut args = args();
     if args.len() > 1 {
-        let flags = Flags::parse_args(args);
+        let flags = match Flags::parse_args(args) {
+            Ok(flags) => flags,
+            Err(message) => {
+                eprintln!(":: {message}");
+                std::process::exit(1)
+            },
+        };
         for _ in 0..(flags.compile_iters.unwrap_or(1)) {
             black_box(compile(&flags));
         }
     } else {
         let program_name = args.nth(0);
         if let Some(program_name) = program_name {
-            println!("usage:\n{program_name} <file> [ flags... ]")
+            println!("usage:\n{program_name} <file>... [ flags... ]")
         }
     }
 }
@@ -3,9 +3,45 @@ use std::io::{BufWriter, Write};
 
 use crate::ir_gen::cmpld_program::CompiledProgram;
 use crate::ir_gen::ctimeval::CTimeVal;
-use crate::ir_gen::ir::IRNode;
+use crate::ir_gen::ir::{Condition, IRNode};
 
 
+/// Condition code suffix for `setcc`/`jcc`
+fn condition_code(condition: Condition) -> &'static str {
+    match condition {
+        Condition::Eq => "e",
+        Condition::NotEq => "ne",
+        Condition::Less => "b",
+        Condition::LessEq => "be",
+        Condition::Greater => "a",
+        Condition::GreaterEq => "ae",
+        Condition::SignedLess => "l",
+        Condition::SignedLessEq => "le",
+        Condition::SignedGreater => "g",
+        Condition::SignedGreaterEq => "ge",
+    }
+}
+
+fn inverse_condition_code(condition: Condition) -> &'static str {
+    match condition {
+        Condition::Eq => "ne",
+        Condition::NotEq => "e",
+        Condition::Less => "ae",
+        Condition::LessEq => "a",
+        Condition::Greater => "be",
+        Condition::GreaterEq => "b",
+        Condition::SignedLess => "ge",
+        Condition::SignedLessEq => "g",
+        Condition::SignedGreater => "le",
+        Condition::SignedGreaterEq => "l",
+    }
+}
+
+/// `push` and `mov qword [..]` sign extend their immediates from 32 bits, bigger ones go through `rax`
+fn fits_imm32(int: u64) -> bool {
+    int as i64 == int as i64 as i32 as i64
+}
+
 pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram) -> Result<(), std::io::Error> {
     let package_name = cprog.get_package_name().unwrap_or("Main");
 
@@ -19,17 +55,13 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
 
     writeln!(out, "section .rodata")?;
     for (string, pos) in cprog.static_strings_iter() {
-        write!(out, "STR_{}: db ", pos)?;
-
-        for (i, ch) in string.chars().enumerate() {
-            if string.len() > (i + 1) {
-                write!(out, "{},", ch as u8)?;
-            } else {
-                write!(out, "{}", ch as u8)?;
-            }
+        // the UTF-8 bytes, not the characters
+        let bytes: Vec<String> = string.bytes().map(|byte| byte.to_string()).collect();
+        if bytes.is_empty() {
+            writeln!(out, "STR_{}: db 0", pos)?;
+        } else {
+            writeln!(out, "STR_{}: db {}", pos, bytes.join(","))?;
         }
-
-        writeln!(out)?;
     }
     
     writeln!(out, "section .data")?;
@@ -45,6 +77,7 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
 
             match global.init {
                 CTimeVal::Int(int) => writeln!(out, "GLOB_{} equ {int}", global.pos)?,
+                CTimeVal::Bool(boolean) => writeln!(out, "GLOB_{} equ {}", global.pos, boolean as u8)?,
                 CTimeVal::Function { address, .. } => writeln!(out, "GLOB_{} equ OP_{address}", global.pos)?,
                 CTimeVal::StringSlice(pointer, len) => {
                     writeln!(out, "GLOB_{} equ STR_{pointer}", global.pos)?;
@@ -57,6 +90,7 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
 
             match global.init {
                 CTimeVal::Int(int) => writeln!(out, "GLOB_{}: dq {int}", global.pos)?,
+                CTimeVal::Bool(boolean) => writeln!(out, "GLOB_{}: dq {}", global.pos, boolean as u8)?,
                 CTimeVal::Function { address, .. } => writeln!(out, "GLOB_{}: dq OP_{address}", global.pos)?,
                 CTimeVal::StringSlice(pointer, len) => {
                     write!(out, "GLOB_{}: ", global.pos)?;
@@ -80,12 +114,23 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
             IRNode::CallFromOffset(offset) => writeln!(out, "    OP_{i}: call OP_{}", (i as i64) + offset)?,
             IRNode::JumpFromOffset(offset) => writeln!(out, "    OP_{i}: jmp OP_{}", (i as i64) + offset)?,
             IRNode::PushAddressFromOffset(offset) => writeln!(out, "    OP_{i}: push qword OP_{}", (i as i64) + offset)?,
-            IRNode::Push64(int) => writeln!(out, "    OP_{i}: push qword {int}")?,
+            IRNode::Push64(int) if fits_imm32(*int) => writeln!(out, "    OP_{i}: push qword {int}")?,
+            IRNode::Push64(int) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    mov rax, {int}")?;
+                writeln!(out, "    push rax")?;
+            },
             IRNode::StackAlloc(size) => writeln!(out, "    OP_{i}: sub rsp, {size}")?,
             IRNode::StackDealloc(size) => writeln!(out, "    OP_{i}: add rsp, {size}")?,
-            IRNode::Load64ToStack(int, offset) => writeln!(out, "    OP_{i}: mov qword [rsp+{offset}], {int}")?,
+            IRNode::Load64ToStack(int, offset) if fits_imm32(*int) => writeln!(out, "    OP_{i}: mov qword [rsp+{offset}], {int}")?,
+            IRNode::Load64ToStack(int, offset) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    mov rax, {int}")?;
+                writeln!(out, "    mov [rsp+{offset}], rax")?;
+            },
             IRNode::GlobalReadPush64(offset) => writeln!(out, "    OP_{i}: push qword [GLOB_{offset}]")?,
             IRNode::StackReadPush64(offset) => writeln!(out, "    OP_{i}: push qword [rsp+{offset}]")?,
+            IRNode::PopGlobal64(offset) => writeln!(out, "    OP_{i}: pop qword [GLOB_{offset}]")?,
             
             IRNode::PushStaticStringPointer(pos) => {
                 writeln!(out, "OP_{i}:")?;
@@ -146,6 +191,14 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
                 writeln!(out, "    cmp rax, {expected}")?;
                 writeln!(out, "    jne OP_{}", (i as i64) + offset)?;
             },
+
+            IRNode::JumpIfNotCmp64FromOffset(condition, offset) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    cmp rax, rbx")?;
+                writeln!(out, "    j{} OP_{}", inverse_condition_code(*condition), (i as i64) + offset)?;
+            },
             
             IRNode::PushStackPointer(offset) => {
                 writeln!(out, "OP_{i}:")?;
@@ -153,12 +206,52 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
                 writeln!(out, "    push rax")?;
             },
 
+            IRNode::PushGlobalPointer(pos) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    lea rax, [rel GLOB_{pos}]")?;
+                writeln!(out, "    push rax")?;
+            },
+
             IRNode::Deref64 => {
                 writeln!(out, "OP_{i}:")?;
                 writeln!(out, "    pop rax")?;
                 writeln!(out, "    push qword [rax]")?;
             },
 
+            IRNode::Deref8 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    movzx eax, byte [rax]")?;
+                writeln!(out, "    push rax")?;
+            },
+
+            IRNode::Deref(size) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rax")?;
+                for word in (0..(size / 8)).rev() {
+                    writeln!(out, "    push qword [rax+{}]", word * 8)?;
+                }
+            },
+
+            IRNode::PopToAddress(size) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    mov rax, [rsp+{size}]")?;
+                for word in 0..(size / 8) {
+                    writeln!(out, "    pop rbx")?;
+                    writeln!(out, "    mov [rax+{}], rbx", word * 8)?;
+                }
+                writeln!(out, "    add rsp, 8")?;
+            },
+
+            IRNode::IndexCheck64 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    cmp [rsp], rax")?;
+                writeln!(out, "    jb .in_bounds")?;
+                writeln!(out, "    call Rt?panic_bounds")?;
+                writeln!(out, ".in_bounds:")?;
+            },
+
             IRNode::StackDeref64(offset) => {
                 writeln!(out, "OP_{i}:")?;
                 writeln!(out, "    mov rax, [rsp+{offset}]")?;
@@ -180,6 +273,103 @@ pub fn gen_asm_x86_64_from_ir(out: &mut BufWriter<File>, cprog: &CompiledProgram
                 writeln!(out, "    sub rax, rbx")?;
                 writeln!(out, "    push rax")?;
             },
+
+            IRNode::Mul64 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    imul rax, rbx")?; // low 64 bits are the same for unsigned
+                writeln!(out, "    push rax")?;
+            },
+
+            IRNode::Div64 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    xor edx, edx")?;
+                writeln!(out, "    div rbx")?;
+                writeln!(out, "    push rax")?;
+            },
+
+            IRNode::Mod64 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    xor edx, edx")?;
+                writeln!(out, "    div rbx")?;
+                writeln!(out, "    push rdx")?;
+            },
+
+            IRNode::IDiv64 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    cqo")?;
+                writeln!(out, "    idiv rbx")?;
+                writeln!(out, "    push rax")?;
+            },
+
+            IRNode::IMod64 => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    cqo")?;
+                writeln!(out, "    idiv rbx")?;
+                writeln!(out, "    push rdx")?;
+            },
+
+            IRNode::Neg64 => writeln!(out, "    OP_{i}: neg qword [rsp]")?,
+            IRNode::Not64 => writeln!(out, "    OP_{i}: not qword [rsp]")?,
+
+            IRNode::Or64 | IRNode::And64 | IRNode::Xor64 => {
+                let instruction = match node {
+                    IRNode::Or64 => "or",
+                    IRNode::And64 => "and",
+                    _ => "xor",
+                };
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    {instruction} rax, rbx")?;
+                writeln!(out, "    push rax")?;
+            },
+
+            IRNode::Shl64 | IRNode::Shr64 | IRNode::Sar64 => {
+                let instruction = match node {
+                    IRNode::Shl64 => "shl",
+                    IRNode::Shr64 => "shr",
+                    _ => "sar",
+                };
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rcx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    {instruction} rax, cl")?; // the count is taken modulo 64
+                writeln!(out, "    push rax")?;
+            },
+
+            IRNode::Truncate64 { size, is_signed } => {
+                writeln!(out, "OP_{i}:")?;
+                match (size, is_signed) {
+                    (1, true) => writeln!(out, "    movsx rax, byte [rsp]")?,
+                    (1, false) => writeln!(out, "    movzx eax, byte [rsp]")?,
+                    (2, true) => writeln!(out, "    movsx rax, word [rsp]")?,
+                    (2, false) => writeln!(out, "    movzx eax, word [rsp]")?,
+                    (4, true) => writeln!(out, "    movsxd rax, dword [rsp]")?,
+                    (4, false) => writeln!(out, "    mov eax, dword [rsp]")?,
+                    _ => unreachable!(),
+                }
+                writeln!(out, "    mov [rsp], rax")?;
+            },
+
+            IRNode::Cmp64(condition) => {
+                writeln!(out, "OP_{i}:")?;
+                writeln!(out, "    pop rbx")?;
+                writeln!(out, "    pop rax")?;
+                writeln!(out, "    cmp rax, rbx")?;
+                writeln!(out, "    set{} al", condition_code(*condition))?;
+                writeln!(out, "    movzx eax, al")?;
+                writeln!(out, "    push rax")?;
+            },
         }
     }
 
@@ -0,0 +1,75 @@
+use std::io::Write;
+
+use crate::ir_gen::cmpld_program::CompiledProgram;
+use crate::ir_gen::typeval::{TypeVal, TypeValEnum};
+
+
+/// Spells a type the way it is written in source, `None` if an importer could not write it
+fn type_expr(typeval: &TypeVal) -> Option<String> {
+    match typeval.as_enum() {
+        TypeValEnum::Unit => Some("void".to_string()),
+        TypeValEnum::Int(kind) => Some(kind.to_string()),
+        TypeValEnum::Bool => Some("bool".to_string()),
+        TypeValEnum::Char => Some("char".to_string()),
+        TypeValEnum::StringSlice => Some("str".to_string()),
+        TypeValEnum::Pointer(sub_typeval) => Some(format!("*{}", type_expr(sub_typeval)?)),
+        TypeValEnum::NullablePointer(sub_typeval) => Some(format!("?*{}", type_expr(sub_typeval)?)),
+        TypeValEnum::Array(element, len) => Some(format!("[{len}]{}", type_expr(element)?)),
+        TypeValEnum::Slice(element) => Some(format!("[]{}", type_expr(element)?)),
+
+        // classes and unions have no name outside of their package yet
+        TypeValEnum::FunctionPointer(..)
+        | TypeValEnum::Closure(..)
+        | TypeValEnum::MethodPointer(..)
+        | TypeValEnum::Class(..)
+        | TypeValEnum::TaggedUnion(..) => None,
+    }
+}
+
+/// Importers read globals through a single word, so `str`, slices and other aggregates can't be imported
+fn is_single_word(typeval: &TypeVal) -> bool {
+    matches!(
+        typeval.as_enum(),
+        TypeValEnum::Int(_) | TypeValEnum::Bool | TypeValEnum::Char
+        | TypeValEnum::FunctionPointer(..) | TypeValEnum::Pointer(..) | TypeValEnum::NullablePointer(..)
+    )
+}
+
+/// Writes every exported global as an `import` block, so importers don't have to re-declare them
+pub fn gen_interface_from_globals(out: &mut impl Write, cprog: &CompiledProgram) -> Result<(), std::io::Error> {
+    let package_name = cprog.get_package_name().unwrap_or("Main");
+
+    writeln!(out, "# interface of package {package_name}, generated by the compiler")?;
+    writeln!(out, "import package {package_name} {{")?;
+
+    for global in cprog.globals_iter().filter(|global| global.is_exported) {
+        let pkg_sep = if global.is_const { '?' } else { '$' };
+        let mangled_name = format!("{package_name}{pkg_sep}{}", global.name);
+
+        let decl = match global.typeval.as_enum() {
+            TypeValEnum::FunctionPointer(param_typevals, return_typeval) if global.is_const => {
+                let param_types: Option<Vec<String>> = param_typevals.iter().map(type_expr).collect();
+                let return_type = match return_typeval.as_enum() {
+                    TypeValEnum::Unit => Some(String::new()),
+                    _ => type_expr(return_typeval).map(|return_type| format!(" :: {return_type}")),
+                };
+
+                param_types.zip(return_type).map(|(param_types, return_type)| format!("{}({}){return_type};", global.name, param_types.join(", ")))
+            },
+
+            _ if !is_single_word(&global.typeval) => None,
+
+            _ => type_expr(&global.typeval).map(|type_expr| {
+                let keyword = if global.is_const { "let" } else { "var" };
+                format!("{keyword} {} {type_expr};", global.name)
+            }),
+        };
+
+        match decl {
+            Some(decl) => writeln!(out, "    {decl} # {mangled_name}")?,
+            None => writeln!(out, "    # {mangled_name} has type `{}`, which cannot be imported", global.typeval)?,
+        }
+    }
+
+    writeln!(out, "}}")
+}
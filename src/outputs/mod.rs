@@ -1,2 +1,3 @@
 
 pub mod asm_x86_64;
+pub mod interface;
@@ -1,53 +1,149 @@
-use crate::lexer::tokens::SourceLocation;
+use core::fmt;
+
+use crate::{lexer::tokens::Span, parser::int_kind::IntKind};
 
 
 #[derive(Debug, Clone, Copy)]
 pub enum Operator {
     Add,
     Sub,
+    Mul,
+    Div,
+    Mod,
     Assign,
     BitOr,
+    BitAnd,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    And,
+    Or,
 }
 
 impl Operator {
     pub fn precedence(&self) -> u8 {
         match self {
-            // Operator::Mul | Operator::Div => 2,
-            Operator::Add | Operator::Sub | Operator::BitOr => 1,
+            Operator::Mul | Operator::Div | Operator::Mod
+            | Operator::BitAnd | Operator::Shl | Operator::Shr => 5,
+            Operator::Add | Operator::Sub | Operator::BitOr | Operator::BitXor => 4,
+            Operator::Eq | Operator::NotEq
+            | Operator::Less | Operator::LessEq
+            | Operator::Greater | Operator::GreaterEq => 3,
+            Operator::And => 2,
+            Operator::Or => 1,
             Operator::Assign => 0,
         }
     }
 }
 
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Div => write!(f, "/"),
+            Operator::Mod => write!(f, "%"),
+            Operator::Assign => write!(f, "="),
+            Operator::BitOr => write!(f, "|"),
+            Operator::BitAnd => write!(f, "&"),
+            Operator::BitXor => write!(f, "^"),
+            Operator::Shl => write!(f, "<<"),
+            Operator::Shr => write!(f, ">>"),
+            Operator::Eq => write!(f, "=="),
+            Operator::NotEq => write!(f, "!="),
+            Operator::Less => write!(f, "<"),
+            Operator::LessEq => write!(f, "<="),
+            Operator::Greater => write!(f, ">"),
+            Operator::GreaterEq => write!(f, ">="),
+            Operator::And => write!(f, "and"),
+            Operator::Or => write!(f, "or"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum IfKind {
     Conditional(Expr),
     ConstBinding { name: String, type_expr: Expr, init: Expr },
 }
 
+#[derive(Debug, Clone)]
+pub enum SelectPattern {
+    Variant { name: String, type_expr: Expr },
+    Else,
+}
+
+/// A variable a function takes from its surroundings, `&name` shares it, `name` copies it
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub name: String,
+    pub by_ref: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectArm {
+    pub pattern: SelectPattern,
+    pub body: Expr,
+    pub span: Span,
+}
+
 // NEVER add cloning to expression enum
 #[derive(Debug, Clone)]
 pub enum ExprEnum {
     IntLit(u64),
+    BoolLit(bool),
+    NullLit,
+    CharLit(u8),
     StringLit(String),
+    ArrayLit(Vec<Expr>), // (elements)
     Block(AstBlock, bool), // (body, return_expr, is_unsafe_block)
     If(Box<IfKind>, AstBlock, Option<AstBlock>), // (condition, body, else_body)
+    While(Box<Expr>, AstBlock), // (condition, body)
+    Loop(AstBlock),
+    Break(Option<Box<Expr>>), // (value)
+    Select(Box<Expr>, Vec<SelectArm>), // (value, arms)
+    OverloadSet(Vec<Expr>, Box<(Option<Expr>, Option<Expr>, Option<Expr>)>), // (candidates, (pre, mid, post))
+    Continue,
     Call(Box<Expr>, Vec<Expr>),
-    Function(AstBlock, Option<Box<Expr>>, Vec<Stmt>), // (body, return_type, params)
+    Function(AstBlock, Option<Box<Expr>>, Vec<Stmt>, Vec<Capture>), // (body, return_type, params, captures)
     Variable(String),
     MemberAccess(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>), // (value, index)
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>), // (value, start, end)
+    Class(Vec<Stmt>), // (fields and methods)
+    ClassLit(Box<Expr>, Vec<Stmt>), // (class, field_inits)
     Reference(Box<Expr>),
     Dereference(Box<Expr>),
+    Negate(Box<Expr>),
+    BitNot(Box<Expr>),
+    Not(Box<Expr>),
+    Cast(Box<Expr>, Box<Expr>), // (value, type_expr)
     BinaryOp { operands: Box<(Expr, Expr)>, op: Operator },
+    CompoundAssign { operands: Box<(Expr, Expr)>, op: Operator }, // `target op= value`
     TypeUnit,
-    TypeUInt64,
+    TypeInt(IntKind),
     TypeString,
+    TypeBool,
+    TypeChar,
+    TypeArray(Box<Expr>, Box<Expr>), // (len, element_type)
+    TypeSlice(Box<Expr>), // (element_type)
+    TypeNullable(Box<Expr>), // (pointer_type)
+    TypeFunction(Vec<Expr>, Option<Box<Expr>>), // (param_types, return_type)
+    TypeSelf, // the class a method belongs to, only written by `self` and `&self` parameters
 }
 
 #[derive(Debug, Clone)]
 pub struct Expr {
     e_enum: ExprEnum,
-    loc: SourceLocation,
+    span: Span,
 }
 
 impl Expr {
@@ -55,8 +151,8 @@ impl Expr {
         &self.e_enum
     }
 
-    pub fn get_loc(&self) -> &SourceLocation {
-        &self.loc
+    pub fn get_span(&self) -> &Span {
+        &self.span
     }
 }
 
@@ -69,13 +165,14 @@ pub enum StmtEnum {
     VarDecl(String, Option<Expr>, Option<Expr>, bool),
 
     PackageDecl(String),
+    ImportDecl(String, Option<Vec<Stmt>>), // (package_name, declarations), without declarations the interface file is used
     AliasDecl(Option<String>, Expr, bool),
 }
 
 #[derive(Debug, Clone)]
 pub struct Stmt {
     s_enum: StmtEnum,
-    loc: SourceLocation,
+    span: Span,
 }
 
 impl Stmt {
@@ -83,8 +180,8 @@ impl Stmt {
         &self.s_enum
     }
 
-    pub fn get_loc(&self) -> &SourceLocation {
-        &self.loc
+    pub fn get_span(&self) -> &Span {
+        &self.span
     }
 }
 
@@ -94,25 +191,30 @@ impl Expr {
             ExprEnum::Block(..) => true,
             ExprEnum::Function(..) => true,
             ExprEnum::If(..) => true,
+            ExprEnum::While(..) => true,
+            ExprEnum::Loop(..) => true,
+            ExprEnum::Class(..) => true,
+            ExprEnum::Select(..) => true,
+            ExprEnum::OverloadSet(..) => true,
             _ => false,
         }
     }
 }
 
 impl ExprEnum {
-    pub fn to_expr(self, loc: SourceLocation) -> Expr {
+    pub fn to_expr(self, span: Span) -> Expr {
         Expr {
             e_enum: self,
-            loc,
+            span,
         }
     }
 }
 
 impl StmtEnum {
-    pub fn to_stmt(self, loc: SourceLocation) -> Stmt {
+    pub fn to_stmt(self, span: Span) -> Stmt {
         Stmt {
             s_enum: self,
-            loc,
+            span,
         }
     }
 }
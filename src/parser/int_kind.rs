@@ -0,0 +1,78 @@
+use std::fmt::Display;
+
+/// Every integer type, values of any kind take a full 64 bit word on the stack
+/// (zero extended if unsigned, sign extended if signed)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntKind {
+    pub const ALL: [IntKind; 8] = [
+        IntKind::I8, IntKind::I16, IntKind::I32, IntKind::I64,
+        IntKind::U8, IntKind::U16, IntKind::U32, IntKind::U64,
+    ];
+
+    pub fn size_of(self) -> usize {
+        match self {
+            IntKind::I8 | IntKind::U8 => 1,
+            IntKind::I16 | IntKind::U16 => 2,
+            IntKind::I32 | IntKind::U32 => 4,
+            IntKind::I64 | IntKind::U64 => 8,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(self, IntKind::I8 | IntKind::I16 | IntKind::I32 | IntKind::I64)
+    }
+
+    pub fn min(self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.size_of() * 8 - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.size_of() * 8 - 1)) - 1
+        } else {
+            (1 << (self.size_of() * 8)) - 1
+        }
+    }
+
+    pub fn contains(self, int: i128) -> bool {
+        (self.min()..=self.max()).contains(&int)
+    }
+
+    /// Truncates to the size of the kind and reinterprets the bits, like `as` does at runtime
+    pub fn wrap(self, int: i128) -> i128 {
+        let bits = self.size_of() * 8;
+        let truncated = int & ((1 << bits) - 1);
+        if self.is_signed() && truncated > self.max() {
+            truncated - (1 << bits)
+        } else {
+            truncated
+        }
+    }
+
+    /// Every value of `self` is also a value of `other`, so no `as` is needed
+    pub fn widens_to(self, other: IntKind) -> bool {
+        self.is_signed() == other.is_signed() && self.size_of() <= other.size_of()
+    }
+}
+
+impl Display for IntKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.is_signed() { 'i' } else { 'u' };
+        write!(f, "{sign}{}", self.size_of() * 8)
+    }
+}
@@ -1,5 +1,6 @@
 
 pub mod parser;
 pub mod ast;
+pub mod int_kind;
 
 
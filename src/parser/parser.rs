@@ -1,46 +1,124 @@
 use std::iter::Peekable;
 
-use crate::{lexer::tokens::{SourceLocation, TokenEnum, Tokens, TokensIterator}, parser::ast::{AstBlock, Expr, ExprEnum, IfKind, Stmt, StmtEnum}, tok::token_other::TokenOther};
+use crate::{diagnostics::{diagnostic::Diagnostic, emitter::DiagnosticEmitter}, lexer::tokens::{SourceLocation, Span, Token, TokenEnum, Tokens, TokensIterator}, parser::ast::{AstBlock, Capture, Expr, ExprEnum, IfKind, Operator, SelectArm, SelectPattern, Stmt, StmtEnum}, tok::token_other::TokenOther};
 
+// errors past this at the same location are almost always a cascade of the first one
+const MAX_ERRORS_PER_LOCATION: usize = 1;
 
 pub struct Parser<'a> {
     tok: Peekable<TokensIterator<'a, TokenOther>>,
-    err_count: usize,
+    diag: DiagnosticEmitter,
     cur_end_tokens: Vec<TokenOther>,
+    last_end: SourceLocation, // end of the last consumed token
+    consumed_count: usize,
+    block_depth: usize,
+    last_err_loc: Option<SourceLocation>,
+    errs_at_loc: usize,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a Tokens<TokenOther>) -> Self {
+    pub fn new(tokens: &'a Tokens<TokenOther>, diag: DiagnosticEmitter) -> Self {
         Self {
             tok: tokens.iter().peekable(),
-            err_count: 0,
+            diag,
             cur_end_tokens: vec![TokenOther::End],
+            last_end: SourceLocation::garbage(),
+            consumed_count: 0,
+            block_depth: 0,
+            last_err_loc: None,
+            errs_at_loc: 0,
         }
     }
     
     pub fn has_errors(&self) -> bool {
-        self.err_count > 0
+        self.diag.has_errors()
     }
 
-    fn emit_diagnostic(&mut self, message: &str, loc: SourceLocation) {
-        self.err_count += 1;
-        println!("(Line {}, Col {}): {}", loc.line, loc.col, message);
+    fn emit_diagnostic(&mut self, span: Span, code: &'static str, message: &str) {
+        if self.last_err_loc == Some(span.start) {
+            self.errs_at_loc += 1;
+            if self.errs_at_loc >= MAX_ERRORS_PER_LOCATION {
+                return
+            }
+        } else {
+            self.last_err_loc = Some(span.start);
+            self.errs_at_loc = 0;
+        }
+
+        self.diag.emit(Diagnostic::error(span, code, message));
     }
 
-    fn emit_diagnostic_here(&mut self, message: &str) {
-        let loc = self.cur_loc();
-        self.emit_diagnostic(message, loc);
+    fn emit_diagnostic_here(&mut self, code: &'static str, message: &str) {
+        let span = self.cur_span();
+        self.emit_diagnostic(span, code, message);
     }
 
     fn cur_loc(&mut self) -> SourceLocation {
+        self.cur_span().start
+    }
+
+    fn cur_span(&mut self) -> Span {
         if let Some(token) = self.tok.peek() {
-            token.get_loc()
+            token.get_span()
         } else {
-            SourceLocation::garbage()
+            Span::point(self.last_end)
+        }
+    }
+
+    /// Span from `start` to the end of the last consumed token
+    fn span_from(&self, start: SourceLocation) -> Span {
+        Span::new(start, self.last_end)
+    }
+
+    fn next_token(&mut self) -> Option<&'a Token<TokenOther>> {
+        let token = self.tok.next();
+        if let Some(token) = token {
+            self.last_end = token.get_span().end;
+            self.consumed_count += 1;
+        }
+        token
+    }
+
+    fn is_end_token(&mut self) -> bool {
+        match self.tok.peek() {
+            Some(token) => match token.as_enum() {
+                TokenEnum::Other(token) => self.cur_end_tokens.contains(token),
+                _ => false,
+            },
+
+            None => false,
+        }
+    }
+
+    /// Panic-mode recovery, skips tokens until the next `;` (consumed), `end`, `else`
+    /// or (outside of blocks) the next `let`, `var` or `public`
+    fn synchronize(&mut self) {
+        while let Some(token) = self.tok.peek() {
+            match token.as_enum() {
+                TokenEnum::Other(TokenOther::Semicolon) => {
+                    self.next_token();
+                    return
+                },
+
+                TokenEnum::Other(TokenOther::End | TokenOther::Else) => return,
+
+                TokenEnum::Other(TokenOther::Let | TokenOther::Var | TokenOther::Public | TokenOther::Import) if self.block_depth == 0 => return,
+
+                _ => {
+                    self.next_token();
+                },
+            }
         }
     }
 
     #[warn(unused_results)]
+    fn peek_other(&mut self) -> Option<TokenOther> {
+        match self.tok.peek()?.as_enum() {
+            TokenEnum::Other(token) => Some(*token),
+            _ => None,
+        }
+    }
+
     fn is_token(&mut self, expected: TokenOther) -> bool {
         match self.tok.peek() {
             Some(token) => {
@@ -63,7 +141,7 @@ impl<'a> Parser<'a> {
     #[warn(unused_results)]
     fn match_token(&mut self, expected: TokenOther) -> Option<&TokenOther> {
         if self.is_token(expected) {
-            let token = self.tok.next().unwrap();
+            let token = self.next_token().unwrap();
             match token.as_enum() {
                 TokenEnum::Other(token) => Some(token),
                 _ => unreachable!(),
@@ -81,7 +159,7 @@ impl<'a> Parser<'a> {
         let mut temp_tok = self.tok.clone();
         let token = temp_tok.peek();
         if self.match_token(expected).is_none() {
-            self.emit_diagnostic_here(if let Some(token) = token {
+            self.emit_diagnostic_here("ExpectedToken", if let Some(token) = token {
                 format!("expected token '{expected}' but got {token}")
             } else {
                 format!("expected token '{expected}' but got EOF")
@@ -92,13 +170,14 @@ impl<'a> Parser<'a> {
     #[allow(dead_code)]
     fn expect_terminator(&mut self) {
         if self.match_terminator().is_none() {
-            self.emit_diagnostic_here("expected terminator: `;`");
+            self.emit_diagnostic_here("ExpectedTerminator", "expected terminator: `;`");
         }
     }
 
     fn unnecessary_terminator(&mut self) {
         let message = "unnecessary terminator `;`, remove it";
 
+        let span = self.cur_span();
         let mut temp_tok = self.tok.clone();
         temp_tok.next();
         if let Some(token) = temp_tok.peek() {
@@ -108,32 +187,32 @@ impl<'a> Parser<'a> {
                     _ => true,
                 }
             ) && self.match_terminator().is_some() {
-                self.emit_diagnostic_here(message);
+                self.emit_diagnostic(span, "UnnecessaryTerminator", message);
             } else {
                 self.match_terminator();
             }
         } else if self.match_terminator().is_some() {
-            self.emit_diagnostic_here(message);
+            self.emit_diagnostic(span, "UnnecessaryTerminator", message);
         }
     }
 
     fn parse_name(&mut self) -> String {
-        let loc = self.cur_loc();
+        let span = self.cur_span();
 
-        match self.tok.next() {
+        match self.next_token() {
             Some(token) => match token.as_enum() {
                 TokenEnum::Ident(name) => {
                     name.clone()
                 },
                 
                 _ => {
-                    self.emit_diagnostic("expected name", loc);
+                    self.emit_diagnostic(span, "ExpectedName", "expected name");
                     "(err)".to_string()
                 },
             },
 
             None => {
-                self.emit_diagnostic_here("expected name, but got EOF");
+                self.emit_diagnostic_here("ExpectedName", "expected name, but got EOF");
                 "(eof)".to_string()
             },
         }
@@ -153,9 +232,19 @@ impl<'a> Parser<'a> {
     pub fn parse(&mut self) -> Vec<Stmt> {
         let mut ast = Vec::new();
         while self.tok.peek().is_some() {
+            let prev_consumed_count = self.consumed_count;
+
             let stmt = self.parse_stmt();
             if let Ok(stmt) = stmt {
                 ast.push(stmt);
+            } else {
+                self.synchronize();
+            }
+
+            // always make progress, a stray `end` or `else` would loop forever otherwise
+            if self.consumed_count == prev_consumed_count {
+                self.emit_diagnostic_here("UnexpectedToken", "unexpected token at the top level");
+                self.next_token();
             }
         }
         
@@ -192,15 +281,26 @@ impl Parser<'_> {
             if self.match_token(TokenOther::As).is_some() {
                 let name = self.parse_name();
                 self.expect_terminator();
-                Ok(StmtEnum::AliasDecl(Some(name), expr, is_call).to_stmt(loc))
+                Ok(StmtEnum::AliasDecl(Some(name), expr, is_call).to_stmt(self.span_from(loc)))
             } else {
                 self.expect_terminator();
-                Ok(StmtEnum::AliasDecl(None, expr, is_call).to_stmt(loc))
+                Ok(StmtEnum::AliasDecl(None, expr, is_call).to_stmt(self.span_from(loc)))
             }
         } else if self.match_token(TokenOther::Package).is_some() {
             let name = self.parse_name();
             self.expect_terminator();
-            Ok(StmtEnum::PackageDecl(name).to_stmt(loc))
+            Ok(StmtEnum::PackageDecl(name).to_stmt(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Import).is_some() {
+            // `package` is explicit, modules parsed within the same file may come later
+            self.expect_token(TokenOther::Package);
+            let name = self.parse_name();
+            if self.match_terminator().is_some() {
+                return Ok(StmtEnum::ImportDecl(name, None).to_stmt(self.span_from(loc)))
+            }
+
+            self.expect_token(TokenOther::OBrace);
+            let decls = self.parse_import_decls()?;
+            Ok(StmtEnum::ImportDecl(name, Some(decls)).to_stmt(self.span_from(loc)))
         } else {
 
             let expr = self.parse_expr()?;
@@ -224,7 +324,7 @@ impl Parser<'_> {
                 false
             };
 
-            Ok(StmtEnum::Expr(expr, is_final_expr).to_stmt(loc))
+            Ok(StmtEnum::Expr(expr, is_final_expr).to_stmt(self.span_from(loc)))
         }
 
     }
@@ -239,7 +339,7 @@ impl Parser<'_> {
         };
 
         if self.match_terminator().is_some() {
-            Ok(StmtEnum::ConstDecl(name, None, type_expr, is_exported).to_stmt(loc))
+            Ok(StmtEnum::ConstDecl(name, None, type_expr, is_exported).to_stmt(self.span_from(loc)))
         } else if self.match_token(TokenOther::ColonColon).is_some() {
             let expr = self.parse_expr()?;
             if expr.is_block() {
@@ -247,10 +347,10 @@ impl Parser<'_> {
             } else {
                 self.expect_terminator();
             }
-            Ok(StmtEnum::ConstDecl(name, Some(expr), type_expr, is_exported).to_stmt(loc))
+            Ok(StmtEnum::ConstDecl(name, Some(expr), type_expr, is_exported).to_stmt(self.span_from(loc)))
         } else {
-            self.emit_diagnostic_here("expected either `;`, `::` or type annotation (use `::` for initializer)");
-            Ok(StmtEnum::ConstDecl(name, None, type_expr, is_exported).to_stmt(loc))
+            self.emit_diagnostic_here("ExpectedInitializer", "expected either `;`, `::` or type annotation (use `::` for initializer)");
+            Ok(StmtEnum::ConstDecl(name, None, type_expr, is_exported).to_stmt(self.span_from(loc)))
         }
     }
     
@@ -264,7 +364,7 @@ impl Parser<'_> {
         };
 
         if self.match_terminator().is_some() {
-            Ok(StmtEnum::VarDecl(name, None, type_expr, is_exported).to_stmt(loc))
+            Ok(StmtEnum::VarDecl(name, None, type_expr, is_exported).to_stmt(self.span_from(loc)))
         } else if self.match_token(TokenOther::ColonColon).is_some() {
             let expr = self.parse_expr()?;
             if expr.is_block() {
@@ -272,10 +372,10 @@ impl Parser<'_> {
             } else {
                 self.expect_terminator();
             }
-            Ok(StmtEnum::VarDecl(name, Some(expr), type_expr, is_exported).to_stmt(loc))
+            Ok(StmtEnum::VarDecl(name, Some(expr), type_expr, is_exported).to_stmt(self.span_from(loc)))
         } else {
-            self.emit_diagnostic_here("expected either `;`, `::` or type annotation (use `::` for initializer)");
-            Ok(StmtEnum::VarDecl(name, None, type_expr, is_exported).to_stmt(loc))
+            self.emit_diagnostic_here("ExpectedInitializer", "expected either `;`, `::` or type annotation (use `::` for initializer)");
+            Ok(StmtEnum::VarDecl(name, None, type_expr, is_exported).to_stmt(self.span_from(loc)))
         }
     }
 }
@@ -290,34 +390,58 @@ impl Parser<'_> {
     }
 
     fn parse_expr_internal(&mut self, min_prec: u8) -> Result<Expr, ()> {
-        let mut lhs = self.parse_primary_expr()?;
+        let loc = self.cur_loc();
+        let mut lhs = if self.match_token(TokenOther::Not).is_some() {
+            // `not` binds looser than comparisons, `not x < y` negates the comparison
+            let negated_expr = self.parse_expr_internal(Operator::Eq.precedence())?;
+            ExprEnum::Not(Box::new(negated_expr)).to_expr(self.span_from(loc))
+        } else {
+            self.parse_cast_expr()?
+        };
 
         while let Some(tok) = self.tok.peek() {
+            // compound assignments bind like `=`
             let op = match tok.as_enum() {
-                TokenEnum::Other(tok) => tok.to_operator(),
+                TokenEnum::Other(tok) => tok.to_operator().map(|op| (op, false))
+                    .or(tok.to_compound_operator().map(|op| (op, true))),
                 _ => None,
             };
 
-            let Some(op) = op else { break };
+            let Some((op, is_compound)) = op else { break };
 
-            let prec = op.precedence();
+            let prec = if is_compound { Operator::Assign.precedence() } else { op.precedence() };
             if prec < min_prec {
                 break
             }
 
-            self.tok.next(); // consume operator
+            self.next_token(); // consume operator
 
             let rhs = self.parse_expr_internal(prec + 1)?;
 
-            lhs = ExprEnum::BinaryOp {
-                operands: Box::new((lhs, rhs)),
-                op,
-            }.to_expr(self.cur_loc());
+            let start = lhs.get_span().start;
+            let operands = Box::new((lhs, rhs));
+            lhs = if is_compound {
+                ExprEnum::CompoundAssign { operands, op }
+            } else {
+                ExprEnum::BinaryOp { operands, op }
+            }.to_expr(self.span_from(start));
         }
 
         Ok(lhs)
     }
 
+    /// `value as type`, binds tighter than any binary operator
+    fn parse_cast_expr(&mut self) -> Result<Expr, ()> {
+        let mut expr = self.parse_primary_expr()?;
+        while self.match_token(TokenOther::As).is_some() {
+            let type_expr = self.parse_type_expr()?;
+            let start = expr.get_span().start;
+            expr = ExprEnum::Cast(Box::new(expr), Box::new(type_expr)).to_expr(self.span_from(start));
+        }
+
+        Ok(expr)
+    }
+
     fn parse_primary_expr(&mut self) -> Result<Expr, ()> {
         let mut expr = self.parse_primary_expr2()?;
         loop {
@@ -337,7 +461,30 @@ impl Parser<'_> {
                     } 
                 }
 
-                expr = ExprEnum::Call(Box::new(expr), args).to_expr(self.cur_loc());
+                let start = expr.get_span().start;
+                expr = ExprEnum::Call(Box::new(expr), args).to_expr(self.span_from(start));
+            } else if self.match_token(TokenOther::OBrace).is_some() {
+                // class literal
+                let field_inits = self.parse_fields(true)?;
+                let start = expr.get_span().start;
+                expr = ExprEnum::ClassLit(Box::new(expr), field_inits).to_expr(self.span_from(start));
+            } else if self.match_token(TokenOther::OBracket).is_some() {
+                // `value[index]` or `value[start:end]`, both ends of a slice are optional
+                let index = if self.is_token(TokenOther::Colon) { None } else { Some(self.parse_expr()?) };
+                let start = expr.get_span().start;
+                if self.match_token(TokenOther::Colon).is_some() {
+                    let end = if self.is_token(TokenOther::CBracket) { None } else { Some(Box::new(self.parse_expr()?)) };
+                    self.expect_token(TokenOther::CBracket);
+                    expr = ExprEnum::Slice(Box::new(expr), index.map(Box::new), end).to_expr(self.span_from(start));
+                } else {
+                    self.expect_token(TokenOther::CBracket);
+                    expr = ExprEnum::Index(Box::new(expr), Box::new(index.unwrap())).to_expr(self.span_from(start));
+                }
+            } else if self.match_token(TokenOther::Dot).is_some() {
+                // member of a call result or a literal
+                let name = self.parse_name();
+                let start = expr.get_span().start;
+                expr = ExprEnum::MemberAccess(Box::new(expr), name).to_expr(self.span_from(start));
             } else {
                 break
             }
@@ -349,18 +496,38 @@ impl Parser<'_> {
     fn parse_primary_expr2(&mut self) -> Result<Expr, ()> {
         let loc = self.cur_loc();
         if self.match_token(TokenOther::Ampersand).is_some() {
-            let ref_expr = self.parse_primary_expr2()?;
-            return Ok(ExprEnum::Reference(Box::new(ref_expr)).to_expr(loc));
+            // `&values[i]` takes the element, not the whole array
+            let ref_expr = self.parse_primary_expr()?;
+            return Ok(ExprEnum::Reference(Box::new(ref_expr)).to_expr(self.span_from(loc)));
         } else if self.match_token(TokenOther::Star).is_some() {
             let deref_expr = self.parse_primary_expr2()?;
-            return Ok(ExprEnum::Dereference(Box::new(deref_expr)).to_expr(loc));
+            return Ok(ExprEnum::Dereference(Box::new(deref_expr)).to_expr(self.span_from(loc)));
+        } else if self.match_token(TokenOther::Minus).is_some() {
+            // calls and member accesses belong to the negated value
+            let negated_expr = self.parse_primary_expr()?;
+            return Ok(ExprEnum::Negate(Box::new(negated_expr)).to_expr(self.span_from(loc)));
+        } else if self.match_token(TokenOther::Tilde).is_some() {
+            let inverted_expr = self.parse_primary_expr()?;
+            return Ok(ExprEnum::BitNot(Box::new(inverted_expr)).to_expr(self.span_from(loc)));
+        } else if self.match_token(TokenOther::OBracket).is_some() {
+            return self.parse_bracket_expr(loc);
+        } else if self.is_token(TokenOther::PlusPlus) || self.is_token(TokenOther::MinusMinus) {
+            // `++x` is `x += 1`
+            let op = if self.match_token(TokenOther::PlusPlus).is_some() { Operator::Add } else { self.next_token(); Operator::Sub };
+            let one = ExprEnum::IntLit(1).to_expr(self.span_from(loc));
+            let target = self.parse_primary_expr()?;
+            return Ok(ExprEnum::CompoundAssign { operands: Box::new((target, one)), op }.to_expr(self.span_from(loc)));
+        } else if self.match_token(TokenOther::Question).is_some() {
+            let pointer_type = self.parse_primary_expr2()?;
+            return Ok(ExprEnum::TypeNullable(Box::new(pointer_type)).to_expr(self.span_from(loc)));
         }
         
         let mut expr = self.parse_secondary_expr()?;
         loop {
             if self.match_token(TokenOther::Dot).is_some() {
                 let name = self.parse_name();
-                expr = ExprEnum::MemberAccess(Box::new(expr), name).to_expr(self.cur_loc());
+                let start = expr.get_span().start;
+                expr = ExprEnum::MemberAccess(Box::new(expr), name).to_expr(self.span_from(start));
             } else {
                 break
             }
@@ -369,6 +536,276 @@ impl Parser<'_> {
         Ok(expr)
     }
 
+    /// Parses `[]T`, `[N]T` or an array literal `[a, b, ...]` after the opening `[`
+    fn parse_bracket_expr(&mut self, loc: SourceLocation) -> Result<Expr, ()> {
+        if self.match_token(TokenOther::CBracket).is_some() {
+            let element_type = self.parse_primary_expr2()?;
+            return Ok(ExprEnum::TypeSlice(Box::new(element_type)).to_expr(self.span_from(loc)));
+        }
+
+        let mut elements = Vec::new();
+        loop {
+            elements.push(self.parse_expr()?);
+            if self.match_token(TokenOther::CBracket).is_some() {
+                break
+            }
+
+            self.expect_token(TokenOther::Comma);
+            if self.match_token(TokenOther::CBracket).is_some() {
+                break // trailing comma
+            }
+        }
+
+        // a single element followed by a type is the length of an array type, a literal is never followed by one
+        if elements.len() == 1 && self.is_type_start() {
+            let len = elements.pop().unwrap();
+            let element_type = self.parse_primary_expr2()?;
+            return Ok(ExprEnum::TypeArray(Box::new(len), Box::new(element_type)).to_expr(self.span_from(loc)));
+        }
+
+        Ok(ExprEnum::ArrayLit(elements).to_expr(self.span_from(loc)))
+    }
+
+    fn is_type_start(&mut self) -> bool {
+        match self.tok.peek().map(|token| token.as_enum()) {
+            Some(TokenEnum::Ident(..)) => true,
+            Some(TokenEnum::Other(other)) => matches!(other,
+                TokenOther::TypeVoid | TokenOther::TypeInt(..) | TokenOther::TypeString | TokenOther::TypeBool | TokenOther::TypeChar
+                | TokenOther::OBracket | TokenOther::Star | TokenOther::Question | TokenOther::OParen
+            ),
+            _ => false,
+        }
+    }
+
+    /// Parses `name type, ...` and `name :: method, ...` (class declarations) or `name :: value, ...` (class literals)
+    /// up to and including the closing `}`
+    fn parse_fields(&mut self, is_init: bool) -> Result<Vec<Stmt>, ()> {
+        let mut fields = Vec::new();
+        loop {
+            if self.match_token(TokenOther::CBrace).is_some() {
+                break
+            } else if self.tok.peek().is_none() {
+                self.emit_diagnostic_here("ExpectedToken", "expected `}` to close the fields, but got EOF");
+                return Err(())
+            }
+
+            let field_loc = self.cur_loc();
+            let name = self.parse_name();
+            let field = if !is_init && self.match_token(TokenOther::ColonColon).is_some() {
+                let init = self.parse_expr()?;
+                StmtEnum::ConstDecl(name, Some(init), None, false)
+            } else if is_init {
+                self.expect_token(TokenOther::ColonColon);
+                let init = self.parse_expr()?;
+                StmtEnum::VarDecl(name, Some(init), None, false)
+            } else {
+                let type_expr = self.parse_type_expr()?;
+                StmtEnum::VarDecl(name, None, Some(type_expr), false)
+            };
+            fields.push(field.to_stmt(self.span_from(field_loc)));
+
+            if !self.is_token(TokenOther::CBrace) {
+                self.expect_token(TokenOther::Comma);
+            }
+        }
+
+        Ok(fields)
+    }
+
+    /// Parses `name(type, ...) :: type;` (functions), `let name type;` and `var name type;`
+    /// up to and including the closing `}`
+    fn parse_import_decls(&mut self) -> Result<Vec<Stmt>, ()> {
+        let mut decls = Vec::new();
+        loop {
+            if self.match_token(TokenOther::CBrace).is_some() {
+                break
+            } else if self.tok.peek().is_none() {
+                self.emit_diagnostic_here("ExpectedToken", "expected `}` to close the import, but got EOF");
+                return Err(())
+            }
+
+            let decl_loc = self.cur_loc();
+            let decl = if self.match_token(TokenOther::Let).is_some() {
+                let name = self.parse_name();
+                let type_expr = self.parse_type_expr()?;
+                StmtEnum::ConstDecl(name, None, Some(type_expr), false)
+            } else if self.match_token(TokenOther::Var).is_some() {
+                let name = self.parse_name();
+                let type_expr = self.parse_type_expr()?;
+                StmtEnum::VarDecl(name, None, Some(type_expr), false)
+            } else {
+                let name = self.parse_name();
+                let type_loc = self.cur_loc();
+                self.expect_token(TokenOther::OParen);
+
+                let mut param_types = Vec::new();
+                while self.match_token(TokenOther::CParen).is_none() {
+                    if self.tok.peek().is_none() {
+                        self.emit_diagnostic_here("ExpectedToken", "expected `)` to close the parameters, but got EOF");
+                        return Err(())
+                    }
+
+                    param_types.push(self.parse_type_expr()?);
+                    if !self.is_token(TokenOther::CParen) {
+                        self.expect_token(TokenOther::Comma);
+                    }
+                }
+
+                let return_type = if self.match_token(TokenOther::ColonColon).is_some() {
+                    Some(Box::new(self.parse_type_expr()?))
+                } else {
+                    None
+                };
+
+                let type_expr = ExprEnum::TypeFunction(param_types, return_type).to_expr(self.span_from(type_loc));
+                StmtEnum::ConstDecl(name, None, Some(type_expr), false)
+            };
+
+            self.expect_terminator();
+            decls.push(decl.to_stmt(self.span_from(decl_loc)));
+        }
+
+        Ok(decls)
+    }
+
+    /// `self` or `&self` as the first parameter of a method
+    fn is_receiver(&mut self) -> bool {
+        let mut temp_tok = self.tok.clone();
+        if let Some(tok) = temp_tok.peek()
+            && let TokenEnum::Other(TokenOther::Ampersand) = tok.as_enum() {
+            temp_tok.next();
+        }
+
+        let Some(tok) = temp_tok.next() else {
+            return false
+        };
+        let is_self = matches!(tok.as_enum(), TokenEnum::Ident(name) if name == "self");
+        is_self && temp_tok.peek().is_some_and(|tok| matches!(tok.as_enum(), TokenEnum::Other(TokenOther::Comma | TokenOther::CParen)))
+    }
+
+    /// `self` takes the object by value, `&self` by pointer
+    fn parse_receiver(&mut self) -> Stmt {
+        let loc = self.cur_loc();
+        let by_ref = self.match_token(TokenOther::Ampersand).is_some();
+        let name = self.parse_name();
+
+        let mut type_expr = ExprEnum::TypeSelf.to_expr(self.span_from(loc));
+        if by_ref {
+            type_expr = ExprEnum::Dereference(Box::new(type_expr)).to_expr(self.span_from(loc));
+        }
+
+        StmtEnum::ConstDecl(name, None, Some(type_expr), false).to_stmt(self.span_from(loc))
+    }
+
+    /// Parses `&name, name, ...` up to and including the closing `}`
+    fn parse_captures(&mut self) -> Vec<Capture> {
+        let mut captures = Vec::new();
+        while self.match_token(TokenOther::CBrace).is_none() {
+            if self.tok.peek().is_none() {
+                self.emit_diagnostic_here("ExpectedToken", "expected `}` to close the captures, but got EOF");
+                break
+            }
+
+            let capture_loc = self.cur_loc();
+            let by_ref = self.match_token(TokenOther::Ampersand).is_some();
+            let name = self.parse_name();
+            captures.push(Capture { name, by_ref, span: self.span_from(capture_loc) });
+
+            if !self.is_token(TokenOther::CBrace) {
+                self.expect_token(TokenOther::Comma);
+            }
+        }
+
+        captures
+    }
+
+    /// Parses `name type => value, ...` and `else => value` up to and including the closing `}`
+    fn parse_select_arms(&mut self) -> Result<Vec<SelectArm>, ()> {
+        let mut arms = Vec::new();
+        loop {
+            if self.match_token(TokenOther::CBrace).is_some() {
+                break
+            } else if self.tok.peek().is_none() {
+                self.emit_diagnostic_here("ExpectedToken", "expected `}` to close the arms, but got EOF");
+                return Err(())
+            }
+
+            let arm_loc = self.cur_loc();
+            let pattern = if self.match_token(TokenOther::Else).is_some() {
+                SelectPattern::Else
+            } else {
+                let name = self.parse_name();
+                let type_expr = self.parse_type_expr()?;
+                SelectPattern::Variant { name, type_expr }
+            };
+
+            self.expect_token(TokenOther::FatArrow);
+            let body = self.parse_expr()?;
+
+            // blocks already have an end, so the comma is optional after them
+            let needs_comma = !body.is_block();
+            arms.push(SelectArm { pattern, body, span: self.span_from(arm_loc) });
+
+            if self.match_token(TokenOther::Comma).is_none() && needs_comma && !self.is_token(TokenOther::CBrace) {
+                self.expect_token(TokenOther::Comma);
+            }
+        }
+
+        Ok(arms)
+    }
+
+    /// Parses `function(type, ...), ...` and `pre|mid|post => function` up to and including the closing `}`
+    #[allow(clippy::type_complexity)]
+    fn parse_overload_set(&mut self) -> Result<(Vec<Expr>, (Option<Expr>, Option<Expr>, Option<Expr>)), ()> {
+        let mut candidates = Vec::new();
+        let mut meta_funcs = (None, None, None);
+        loop {
+            if self.match_token(TokenOther::CBrace).is_some() {
+                break
+            } else if self.tok.peek().is_none() {
+                self.emit_diagnostic_here("ExpectedToken", "expected `}` to close the overload set, but got EOF");
+                return Err(())
+            }
+
+            // meta functions are named, candidates never are followed by `=>`
+            let mut temp_tok = self.tok.clone();
+            temp_tok.next();
+            let is_meta_func = matches!(temp_tok.peek().map(|token| token.as_enum()), Some(TokenEnum::Other(TokenOther::FatArrow)));
+
+            if is_meta_func {
+                let meta_span = self.cur_span();
+                let name = self.parse_name();
+                self.expect_token(TokenOther::FatArrow);
+                let func = self.parse_expr()?;
+
+                let slot = match name.as_str() {
+                    "pre" => Some(&mut meta_funcs.0),
+                    "mid" => Some(&mut meta_funcs.1),
+                    "post" => Some(&mut meta_funcs.2),
+                    _ => {
+                        self.emit_diagnostic(meta_span, "UnknownMetaFunction", format!("unknown meta function `{name}`, expected `pre`, `mid` or `post`").as_str());
+                        None
+                    },
+                };
+
+                if let Some(slot) = slot {
+                    if slot.is_some() {
+                        self.emit_diagnostic(meta_span, "DuplicateMetaFunction", format!("meta function `{name}` is already declared").as_str());
+                    }
+                    *slot = Some(func);
+                }
+            } else {
+                candidates.push(self.parse_expr()?);
+            }
+
+            if !self.is_token(TokenOther::CBrace) {
+                self.expect_token(TokenOther::Comma);
+            }
+        }
+
+        Ok((candidates, meta_funcs))
+    }
+
     fn parse_secondary_expr(&mut self) -> Result<Expr, ()> {
         let loc = self.cur_loc();
         
@@ -385,21 +822,31 @@ impl Parser<'_> {
                 false
             };
 
-            if self.is_token(TokenOther::CParen) || (self.is_name() && next_is_colon) {
+            let is_receiver = self.is_receiver();
+            if self.is_token(TokenOther::CParen) || is_receiver || (self.is_name() && next_is_colon) {
                 let mut params = Vec::new();
+                if is_receiver {
+                    params.push(self.parse_receiver());
+                    if !self.is_token(TokenOther::CParen) {
+                        self.expect_token(TokenOther::Comma);
+                    }
+                }
+
                 if self.match_token(TokenOther::CParen).is_none() {
                     
+                    let param_loc = self.cur_loc();
                     let name = self.parse_name();
                     self.expect_token(TokenOther::Colon);
                     let type_expr = self.parse_type_expr()?;
-                    params.push(StmtEnum::ConstDecl(name, None, Some(type_expr), false).to_stmt(loc));
+                    params.push(StmtEnum::ConstDecl(name, None, Some(type_expr), false).to_stmt(self.span_from(param_loc)));
 
                     loop {
                         if self.match_token(TokenOther::Comma).is_some() {
+                            let param_loc = self.cur_loc();
                             let name = self.parse_name();
                             self.expect_token(TokenOther::Colon);
                             let type_expr = self.parse_type_expr()?;
-                            params.push(StmtEnum::ConstDecl(name, None, Some(type_expr), false).to_stmt(loc));
+                            params.push(StmtEnum::ConstDecl(name, None, Some(type_expr), false).to_stmt(self.span_from(param_loc)));
                         } else {
                             self.expect_token(TokenOther::CParen);
                             break
@@ -407,6 +854,13 @@ impl Parser<'_> {
                     }
                 }
 
+                // `{&a, b}` after the parameters, a body never starts with `{`
+                let captures = if self.match_token(TokenOther::OBrace).is_some() {
+                    self.parse_captures()
+                } else {
+                    Vec::new()
+                };
+
                 let return_type = if self.match_token(TokenOther::Colon).is_some() {
                     let type_expr = self.parse_type_expr()?;
                     self.expect_token(TokenOther::Colon);
@@ -416,12 +870,15 @@ impl Parser<'_> {
                 };
 
                 let result = self.parse_block(vec![TokenOther::End])?;
-                Ok(ExprEnum::Function(result.0, return_type, params).to_expr(loc))
+                Ok(ExprEnum::Function(result.0, return_type, params, captures).to_expr(self.span_from(loc)))
             } else {
-                todo!()
+                // just parentheses for grouping
+                let expr = self.parse_expr()?;
+                self.expect_token(TokenOther::CParen);
+                Ok(expr)
             }
         } else if self.match_token(TokenOther::TypeVoid).is_some() {
-            Ok(ExprEnum::TypeUnit.to_expr(loc))
+            Ok(ExprEnum::TypeUnit.to_expr(self.span_from(loc)))
         } else if self.match_token(TokenOther::If).is_some() {
             self.expect_token(TokenOther::OParen);
 
@@ -444,7 +901,44 @@ impl Parser<'_> {
                 _ => unreachable!(),
             };
 
-            Ok(ExprEnum::If(Box::new(if_kind), body, else_body).to_expr(loc))
+            Ok(ExprEnum::If(Box::new(if_kind), body, else_body).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::While).is_some() {
+            self.expect_token(TokenOther::OParen);
+            let condition = self.parse_expr()?;
+            self.expect_token(TokenOther::CParen);
+
+            let result = self.parse_block(vec![TokenOther::End])?;
+            Ok(ExprEnum::While(Box::new(condition), result.0).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Loop).is_some() {
+            let result = self.parse_block(vec![TokenOther::End])?;
+            Ok(ExprEnum::Loop(result.0).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Break).is_some() {
+            let value = if self.is_terminator() || self.is_end_token() {
+                None
+            } else {
+                Some(Box::new(self.parse_expr()?))
+            };
+
+            Ok(ExprEnum::Break(value).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Class).is_some() {
+            self.expect_token(TokenOther::OBrace);
+            let fields = self.parse_fields(false)?;
+            Ok(ExprEnum::Class(fields).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Select).is_some() {
+            if self.match_token(TokenOther::OBrace).is_some() {
+                let (candidates, meta_funcs) = self.parse_overload_set()?;
+                return Ok(ExprEnum::OverloadSet(candidates, Box::new(meta_funcs)).to_expr(self.span_from(loc)))
+            }
+
+            self.expect_token(TokenOther::OParen);
+            let value = self.parse_expr()?;
+            self.expect_token(TokenOther::CParen);
+
+            self.expect_token(TokenOther::OBrace);
+            let arms = self.parse_select_arms()?;
+            Ok(ExprEnum::Select(Box::new(value), arms).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Continue).is_some() {
+            Ok(ExprEnum::Continue.to_expr(self.span_from(loc)))
         } else if self.is_token(TokenOther::Unsafe) || self.is_token(TokenOther::Do) {
 
             let is_unsafe_block = if self.match_token(TokenOther::Unsafe).is_some() {
@@ -457,35 +951,60 @@ impl Parser<'_> {
             let result = self.parse_block(vec![TokenOther::End])?;
             let block = result.0;
 
-            Ok(ExprEnum::Block(block, is_unsafe_block).to_expr(loc))
-        } else if self.match_token(TokenOther::TypeUInt64).is_some() {
-            Ok(ExprEnum::TypeUInt64.to_expr(loc))
+            Ok(ExprEnum::Block(block, is_unsafe_block).to_expr(self.span_from(loc)))
+        } else if let Some(TokenOther::TypeInt(kind)) = self.peek_other() {
+            self.next_token();
+            Ok(ExprEnum::TypeInt(kind).to_expr(self.span_from(loc)))
         } else if self.match_token(TokenOther::TypeString).is_some() {
-            Ok(ExprEnum::TypeString.to_expr(loc))
+            Ok(ExprEnum::TypeString.to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::TypeBool).is_some() {
+            Ok(ExprEnum::TypeBool.to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::TypeChar).is_some() {
+            Ok(ExprEnum::TypeChar.to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::True).is_some() {
+            Ok(ExprEnum::BoolLit(true).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::False).is_some() {
+            Ok(ExprEnum::BoolLit(false).to_expr(self.span_from(loc)))
+        } else if self.match_token(TokenOther::Null).is_some() {
+            Ok(ExprEnum::NullLit.to_expr(self.span_from(loc)))
         } else if let Some(token) = self.tok.peek() {
             match token.as_enum() {
                 TokenEnum::IntLiteral(int) => {
-                    self.tok.next();
-                    Ok(ExprEnum::IntLit(*int).to_expr(loc))
+                    self.next_token();
+                    Ok(ExprEnum::IntLit(*int).to_expr(self.span_from(loc)))
                 }
                 TokenEnum::StringLiteral(string) => {
-                    self.tok.next();
-                    Ok(ExprEnum::StringLit(string.clone()).to_expr(loc))
+                    self.next_token();
+                    Ok(ExprEnum::StringLit(string.clone()).to_expr(self.span_from(loc)))
+                }
+                TokenEnum::CharLiteral(string) => {
+                    // the lexer only lets single ASCII characters through
+                    let byte = string.as_bytes()[0];
+                    self.next_token();
+                    Ok(ExprEnum::CharLit(byte).to_expr(self.span_from(loc)))
                 }
                 TokenEnum::Ident(name) => {
-                    self.tok.next();
-                    Ok(ExprEnum::Variable(name.clone()).to_expr(loc))
+                    self.next_token();
+                    Ok(ExprEnum::Variable(name.clone()).to_expr(self.span_from(loc)))
                 }
-                _ => Err(()),
+                TokenEnum::Error => Err(()), // already reported by the lexer
+                _ => {
+                    let message = format!("expected expression, but got {token}");
+                    self.emit_diagnostic_here("ExpectedExpression", message.as_str());
+                    Err(())
+                },
             }
         } else {
+            self.emit_diagnostic_here("ExpectedExpression", "expected expression, but got EOF");
             Err(())
         }
     }
 
     fn parse_block(&mut self, end_tokens: Vec<TokenOther>) -> Result<(AstBlock, TokenOther), ()> {
         let prev_end_token = self.cur_end_tokens.clone();
+        self.block_depth += 1;
         let result = self.parse_block_internal(end_tokens);
+        self.block_depth -= 1;
         self.cur_end_tokens = prev_end_token;
         result
     }
@@ -500,7 +1019,32 @@ impl Parser<'_> {
 
         let end_token = loop {
 
-            let stmt = self.parse_stmt()?;
+            // empty blocks, or the end of a block after recovering from an error
+            if self.is_end_token() {
+                match self.next_token().unwrap().as_enum() {
+                    TokenEnum::Other(end_token) => break end_token,
+                    _ => unreachable!(),
+                }
+            } else if self.tok.peek().is_none() {
+                let expected: Vec<String> = self.cur_end_tokens.iter().map(|token| format!("`{token}`")).collect();
+                self.emit_diagnostic_here("ExpectedToken", format!("expected {} to close this block, but got EOF", expected.join(" or ")).as_str());
+                return Err(())
+            }
+
+            let prev_consumed_count = self.consumed_count;
+            let stmt = match self.parse_stmt() {
+                Ok(stmt) => stmt,
+                Err(()) => {
+                    self.synchronize();
+
+                    // stuck on a token that is not valid here (like `else` in a `do` block)
+                    if self.consumed_count == prev_consumed_count && !self.is_end_token() {
+                        self.next_token();
+                    }
+
+                    continue
+                },
+            };
 
             if let Some(end_token) = {
                 if let Some(token) = self.tok.peek() {
@@ -524,7 +1068,7 @@ impl Parser<'_> {
                     _ => block.body.push(stmt),
                 }
 
-                self.tok.next();
+                self.next_token();
                 break end_token
             } else {
                 block.body.push(stmt);
@@ -1,6 +1,6 @@
 use core::fmt;
 
-use crate::{lexer::token_map::TokenMap, parser::ast::Operator};
+use crate::{lexer::token_map::TokenMap, parser::{ast::Operator, int_kind::IntKind}};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum TokenOther {
@@ -9,16 +9,31 @@ pub enum TokenOther {
     Var,
     Public,
     Package,
+    Import,
     Alias,
     As,
     If,
     Else,
+    While,
+    Loop,
+    Break,
+    Continue,
     Do,
     End,
     Unsafe,
+    Class,
+    Select,
+    And,
+    Or,
+    Not,
     TypeVoid,
-    TypeUInt64,
+    TypeInt(IntKind),
     TypeString,
+    TypeBool,
+    TypeChar,
+    True,
+    False,
+    Null,
 
     // symbols
     OParen,
@@ -27,15 +42,44 @@ pub enum TokenOther {
     CBrace,
     Semicolon,
     Equal,
+    EqualEqual,
+    FatArrow,
+    Bang,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
     Colon,
     ColonColon,
     Dot,
     Comma,
+    OBracket,
+    CBracket,
     Ampersand,
     Star,
+    Slash,
+    Percent,
     Plus,
     Minus,
     Pipe,
+    Question,
+    Caret,
+    Tilde,
+    LessLess,
+    GreaterGreater,
+    PlusPlus,
+    MinusMinus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    PipeEqual,
+    AmpersandEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
 }
 
 impl TokenOther {
@@ -46,16 +90,33 @@ impl TokenOther {
         token_map.make_keyword("var", TokenOther::Var);
         token_map.make_keyword("public", TokenOther::Public);
         token_map.make_keyword("package", TokenOther::Package);
+        token_map.make_keyword("import", TokenOther::Import);
         token_map.make_keyword("alias", TokenOther::Alias);
         token_map.make_keyword("as", TokenOther::As);
         token_map.make_keyword("if", TokenOther::If);
         token_map.make_keyword("else", TokenOther::Else);
+        token_map.make_keyword("while", TokenOther::While);
+        token_map.make_keyword("loop", TokenOther::Loop);
+        token_map.make_keyword("break", TokenOther::Break);
+        token_map.make_keyword("continue", TokenOther::Continue);
         token_map.make_keyword("do", TokenOther::Do);
         token_map.make_keyword("end", TokenOther::End);
         token_map.make_keyword("unsafe", TokenOther::Unsafe);
+        token_map.make_keyword("class", TokenOther::Class);
+        token_map.make_keyword("select", TokenOther::Select);
+        token_map.make_keyword("and", TokenOther::And);
+        token_map.make_keyword("or", TokenOther::Or);
+        token_map.make_keyword("not", TokenOther::Not);
         token_map.make_keyword("void", TokenOther::TypeVoid);
-        token_map.make_keyword("u64", TokenOther::TypeUInt64);
+        for kind in IntKind::ALL {
+            token_map.make_keyword(kind.to_string().as_str(), TokenOther::TypeInt(kind));
+        }
         token_map.make_keyword("str", TokenOther::TypeString);
+        token_map.make_keyword("bool", TokenOther::TypeBool);
+        token_map.make_keyword("char", TokenOther::TypeChar);
+        token_map.make_keyword("true", TokenOther::True);
+        token_map.make_keyword("false", TokenOther::False);
+        token_map.make_keyword("null", TokenOther::Null);
 
         token_map.make("(", TokenOther::OParen);
         token_map.make(")", TokenOther::CParen);
@@ -63,15 +124,44 @@ impl TokenOther {
         token_map.make("}", TokenOther::CBrace);
         token_map.make(";", TokenOther::Semicolon);
         token_map.make("=", TokenOther::Equal);
+        token_map.make("==", TokenOther::EqualEqual);
+        token_map.make("=>", TokenOther::FatArrow);
+        token_map.make("!", TokenOther::Bang);
+        token_map.make("!=", TokenOther::BangEqual);
+        token_map.make("<", TokenOther::Less);
+        token_map.make("<=", TokenOther::LessEqual);
+        token_map.make(">", TokenOther::Greater);
+        token_map.make(">=", TokenOther::GreaterEqual);
         token_map.make(":", TokenOther::Colon);
         token_map.make("::", TokenOther::ColonColon);
         token_map.make(".", TokenOther::Dot);
         token_map.make(",", TokenOther::Comma);
+        token_map.make("[", TokenOther::OBracket);
+        token_map.make("]", TokenOther::CBracket);
         token_map.make("&", TokenOther::Ampersand);
         token_map.make("*", TokenOther::Star);
+        token_map.make("/", TokenOther::Slash);
+        token_map.make("%", TokenOther::Percent);
         token_map.make("+", TokenOther::Plus);
         token_map.make("-", TokenOther::Minus);
         token_map.make("|", TokenOther::Pipe);
+        token_map.make("?", TokenOther::Question);
+        token_map.make("^", TokenOther::Caret);
+        token_map.make("~", TokenOther::Tilde);
+        token_map.make("<<", TokenOther::LessLess);
+        token_map.make(">>", TokenOther::GreaterGreater);
+        token_map.make("++", TokenOther::PlusPlus);
+        token_map.make("--", TokenOther::MinusMinus);
+        token_map.make("+=", TokenOther::PlusEqual);
+        token_map.make("-=", TokenOther::MinusEqual);
+        token_map.make("*=", TokenOther::StarEqual);
+        token_map.make("/=", TokenOther::SlashEqual);
+        token_map.make("%=", TokenOther::PercentEqual);
+        token_map.make("|=", TokenOther::PipeEqual);
+        token_map.make("&=", TokenOther::AmpersandEqual);
+        token_map.make("^=", TokenOther::CaretEqual);
+        token_map.make("<<=", TokenOther::LessLessEqual);
+        token_map.make(">>=", TokenOther::GreaterGreaterEqual);
 
         token_map
     }
@@ -82,8 +172,40 @@ impl TokenOther {
         match self {
             Self::Plus => Some(Operator::Add),
             Self::Minus => Some(Operator::Sub),
+            Self::Star => Some(Operator::Mul),
+            Self::Slash => Some(Operator::Div),
+            Self::Percent => Some(Operator::Mod),
             Self::Pipe => Some(Operator::BitOr),
+            Self::Ampersand => Some(Operator::BitAnd), // only in binary position, a leading `&` takes a reference
+            Self::Caret => Some(Operator::BitXor),
+            Self::LessLess => Some(Operator::Shl),
+            Self::GreaterGreater => Some(Operator::Shr),
             Self::Equal => Some(Operator::Assign),
+            Self::And => Some(Operator::And),
+            Self::Or => Some(Operator::Or),
+            Self::EqualEqual => Some(Operator::Eq),
+            Self::BangEqual => Some(Operator::NotEq),
+            Self::Less => Some(Operator::Less),
+            Self::LessEqual => Some(Operator::LessEq),
+            Self::Greater => Some(Operator::Greater),
+            Self::GreaterEqual => Some(Operator::GreaterEq),
+            _ => None,
+        }
+    }
+
+    /// The operator of a compound assignment like `+=`
+    pub fn to_compound_operator(&self) -> Option<Operator> {
+        match self {
+            Self::PlusEqual => Some(Operator::Add),
+            Self::MinusEqual => Some(Operator::Sub),
+            Self::StarEqual => Some(Operator::Mul),
+            Self::SlashEqual => Some(Operator::Div),
+            Self::PercentEqual => Some(Operator::Mod),
+            Self::PipeEqual => Some(Operator::BitOr),
+            Self::AmpersandEqual => Some(Operator::BitAnd),
+            Self::CaretEqual => Some(Operator::BitXor),
+            Self::LessLessEqual => Some(Operator::Shl),
+            Self::GreaterGreaterEqual => Some(Operator::Shr),
             _ => None,
         }
     }
@@ -96,16 +218,31 @@ impl fmt::Display for TokenOther {
             TokenOther::Var => write!(f, "var"),
             TokenOther::Public => write!(f, "public"),
             TokenOther::Package => write!(f, "package"),
+            TokenOther::Import => write!(f, "import"),
             TokenOther::Alias => write!(f, "alias"),
             TokenOther::As => write!(f, "as"),
             TokenOther::If => write!(f, "if"),
             TokenOther::Else => write!(f, "else"),
+            TokenOther::While => write!(f, "while"),
+            TokenOther::Loop => write!(f, "loop"),
+            TokenOther::Break => write!(f, "break"),
+            TokenOther::Continue => write!(f, "continue"),
             TokenOther::Do => write!(f, "do"),
             TokenOther::End => write!(f, "end"),
             TokenOther::Unsafe => write!(f, "unsafe"),
+            TokenOther::Class => write!(f, "class"),
+            TokenOther::Select => write!(f, "select"),
+            TokenOther::And => write!(f, "and"),
+            TokenOther::Or => write!(f, "or"),
+            TokenOther::Not => write!(f, "not"),
             TokenOther::TypeVoid => write!(f, "void"),
-            TokenOther::TypeUInt64 => write!(f, "u64"),
+            TokenOther::TypeInt(kind) => write!(f, "{kind}"),
             TokenOther::TypeString => write!(f, "str"),
+            TokenOther::TypeBool => write!(f, "bool"),
+            TokenOther::TypeChar => write!(f, "char"),
+            TokenOther::True => write!(f, "true"),
+            TokenOther::False => write!(f, "false"),
+            TokenOther::Null => write!(f, "null"),
 
             TokenOther::OParen => write!(f, "("),
             TokenOther::CParen => write!(f, ")"),
@@ -113,15 +250,44 @@ impl fmt::Display for TokenOther {
             TokenOther::CBrace => write!(f, "}}"),
             TokenOther::Semicolon => write!(f, ";"),
             TokenOther::Equal => write!(f, "="),
+            TokenOther::EqualEqual => write!(f, "=="),
+            TokenOther::FatArrow => write!(f, "=>"),
+            TokenOther::Bang => write!(f, "!"),
+            TokenOther::BangEqual => write!(f, "!="),
+            TokenOther::Less => write!(f, "<"),
+            TokenOther::LessEqual => write!(f, "<="),
+            TokenOther::Greater => write!(f, ">"),
+            TokenOther::GreaterEqual => write!(f, ">="),
             TokenOther::Colon => write!(f, ":"),
             TokenOther::ColonColon => write!(f, "::"),
             TokenOther::Dot => write!(f, "."),
             TokenOther::Comma => write!(f, ","),
+            TokenOther::OBracket => write!(f, "["),
+            TokenOther::CBracket => write!(f, "]"),
             TokenOther::Ampersand => write!(f, "&"),
             TokenOther::Star => write!(f, "*"),
+            TokenOther::Slash => write!(f, "/"),
+            TokenOther::Percent => write!(f, "%"),
             TokenOther::Plus => write!(f, "+"),
             TokenOther::Minus => write!(f, "-"),
             TokenOther::Pipe => write!(f, "|"),
+            TokenOther::Question => write!(f, "?"),
+            TokenOther::Caret => write!(f, "^"),
+            TokenOther::Tilde => write!(f, "~"),
+            TokenOther::LessLess => write!(f, "<<"),
+            TokenOther::GreaterGreater => write!(f, ">>"),
+            TokenOther::PlusPlus => write!(f, "++"),
+            TokenOther::MinusMinus => write!(f, "--"),
+            TokenOther::PlusEqual => write!(f, "+="),
+            TokenOther::MinusEqual => write!(f, "-="),
+            TokenOther::StarEqual => write!(f, "*="),
+            TokenOther::SlashEqual => write!(f, "/="),
+            TokenOther::PercentEqual => write!(f, "%="),
+            TokenOther::PipeEqual => write!(f, "|="),
+            TokenOther::AmpersandEqual => write!(f, "&="),
+            TokenOther::CaretEqual => write!(f, "^="),
+            TokenOther::LessLessEqual => write!(f, "<<="),
+            TokenOther::GreaterGreaterEqual => write!(f, ">>="),
         }
     }
 }